@@ -0,0 +1,665 @@
+// Двусвязный список на Rc: вперёд узлы держат друг друга сильными ссылками, назад — через Weak,
+// поэтому циклов владения нет и узлы освобождаются без утечек
+#![allow(unused)]
+
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    rc::{Rc, Weak},
+};
+
+use linked_list::{LinkedList, ListError};
+
+type Link<T> = Option<Rc<NodeRc<T>>>;
+
+struct NodeRc<T> {
+    data: T,
+    next: RefCell<Link<T>>,
+    prev: RefCell<Weak<NodeRc<T>>>,
+}
+
+impl<T> NodeRc<T> {
+    fn new(data: T, prev: Weak<NodeRc<T>>, next: Link<T>) -> Rc<Self> {
+        Rc::new(NodeRc {
+            data,
+            next: RefCell::new(next),
+            prev: RefCell::new(prev),
+        })
+    }
+
+    fn next_node(&self) -> Link<T> {
+        self.next.borrow().clone()
+    }
+
+    fn prev_node(&self) -> Link<T> {
+        self.prev.borrow().upgrade()
+    }
+
+    fn set_next(&self, next: Link<T>) {
+        *self.next.borrow_mut() = next;
+    }
+
+    fn set_prev(&self, prev: Option<&Rc<NodeRc<T>>>) {
+        *self.prev.borrow_mut() = prev.map_or_else(Weak::new, Rc::downgrade);
+    }
+}
+
+// Узлы сравниваются, хешируются и печатаются по данным, без учёта связей
+impl<T: PartialEq> PartialEq for NodeRc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl<T: Eq> Eq for NodeRc<T> {}
+
+impl<T: PartialOrd> PartialOrd for NodeRc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.data.partial_cmp(&other.data)
+    }
+}
+
+impl<T: Ord> Ord for NodeRc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.data.cmp(&other.data)
+    }
+}
+
+impl<T: Hash> Hash for NodeRc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for NodeRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.data.fmt(f)
+    }
+}
+
+struct DoublyListNodeRc<T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+}
+
+// Паникует, если очередной узел удерживается снаружи, как pop_head и pop_tail
+struct IntoIterRc<T>(DoublyListNodeRc<T>);
+
+impl<T> Iterator for IntoIterRc<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_head()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIterRc<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_tail()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIterRc<T> {}
+
+// Отдаёт сами узлы: их можно использовать как handle для remove и insert_before
+struct IterRc<T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+}
+
+impl<T> Iterator for IterRc<T> {
+    type Item = Rc<NodeRc<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        self.front.take().inspect(|node| {
+            self.front = node.next_node();
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterRc<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        self.back.take().inspect(|node| {
+            self.back = node.prev_node();
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterRc<T> {}
+
+impl<T> Default for DoublyListNodeRc<T> {
+    fn default() -> Self {
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
+}
+
+impl<T> DoublyListNodeRc<T> {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn iter(&self) -> IterRc<T> {
+        IterRc {
+            front: self.head.clone(),
+            back: self.tail.clone(),
+            len: self.len,
+        }
+    }
+
+    fn peek_tail(&self) -> Option<&T> {
+        self.tail.as_ref().map(|node| &node.data)
+    }
+
+    fn push_tail(&mut self, data: T) {
+        let node = NodeRc::new(data, Weak::new(), None);
+        match self.tail.take() {
+            Some(old_tail) => {
+                node.set_prev(Some(&old_tail));
+                old_tail.set_next(Some(Rc::clone(&node)));
+            }
+            None => self.head = Some(Rc::clone(&node)),
+        }
+        self.tail = Some(node);
+        self.len += 1;
+    }
+
+    // Shared, если последний узел удерживается снаружи, например узлом из iter(); тогда список
+    // не меняется
+    fn try_pop_tail(&mut self) -> Result<Option<T>, ListError> {
+        match self.tail.clone() {
+            Some(tail) => self.take_node(tail).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // Паникует там, где try_pop_tail вернёт ошибку
+    fn pop_tail(&mut self) -> Option<T> {
+        match self.try_pop_tail() {
+            Ok(data) => data,
+            Err(err) => panic!("pop_tail failed: {err}"),
+        }
+    }
+
+    fn try_pop_head(&mut self) -> Result<Option<T>, ListError> {
+        match self.head.clone() {
+            Some(head) => self.take_node(head).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // Идёт с того конца, который ближе к position
+    fn node_at(&self, position: usize) -> Option<Rc<NodeRc<T>>> {
+        if position >= self.len {
+            return None;
+        }
+        if position < self.len / 2 {
+            self.iter().nth(position)
+        } else {
+            self.iter().nth_back(self.len - 1 - position)
+        }
+    }
+
+    fn out_of_bounds(&self, position: usize) -> ListError {
+        ListError::OutOfBounds {
+            position,
+            len: self.len,
+        }
+    }
+
+    // Кроме handle вызывающего, на узел ссылаются предыдущий узел (или голова) и, для последнего
+    // узла, хвост списка. Любая другая ссылка значит, что узел удерживается снаружи
+    fn is_shared(&self, node: &Rc<NodeRc<T>>) -> bool {
+        let is_tail = self.tail.as_ref().is_some_and(|tail| Rc::ptr_eq(tail, node));
+        Rc::strong_count(node) > 2 + usize::from(is_tail)
+    }
+
+    // Узел принадлежит списку, если обратные ссылки ведут от него к голове этого списка.
+    // У удалённого узла обратной ссылки нет, и он сам головой не является
+    fn contains(&self, node: &Rc<NodeRc<T>>) -> bool {
+        let mut first = Rc::clone(node);
+        while let Some(prev) = first.prev_node() {
+            first = prev;
+        }
+        self.head.as_ref().is_some_and(|head| Rc::ptr_eq(head, &first))
+    }
+
+    // Соседи находятся по ссылкам, но принадлежность списку проверяется обходом до головы,
+    // поэтому O(position). StaleHandle, если узел удалён или из другого списка
+    fn remove(&mut self, node: Rc<NodeRc<T>>) -> Result<T, ListError> {
+        if !self.contains(&node) {
+            return Err(ListError::StaleHandle);
+        }
+        self.take_node(node)
+    }
+
+    fn take_node(&mut self, node: Rc<NodeRc<T>>) -> Result<T, ListError> {
+        if self.is_shared(&node) {
+            return Err(ListError::Shared);
+        }
+
+        let prev = node.prev_node();
+        let next = node.next.borrow_mut().take();
+        node.set_prev(None);
+
+        match &next {
+            Some(next) => next.set_prev(prev.as_ref()),
+            None => self.tail = prev.clone(),
+        }
+        match prev {
+            Some(prev) => prev.set_next(next),
+            None => self.head = next,
+        }
+        self.len -= 1;
+
+        Rc::try_unwrap(node)
+            .map(|node| node.data)
+            .map_err(|_| ListError::Shared)
+    }
+
+    // Новый элемент встаёт перед узлом node; StaleHandle, как в remove
+    fn insert_before(&mut self, node: &Rc<NodeRc<T>>, data: T) -> Result<(), ListError> {
+        if !self.contains(node) {
+            return Err(ListError::StaleHandle);
+        }
+        self.link_before(node, data);
+        Ok(())
+    }
+
+    fn link_before(&mut self, node: &Rc<NodeRc<T>>, data: T) {
+        let prev = node.prev_node();
+        let new_node = NodeRc::new(data, Weak::new(), Some(Rc::clone(node)));
+        new_node.set_prev(prev.as_ref());
+        node.set_prev(Some(&new_node));
+        match prev {
+            Some(prev) => prev.set_next(Some(new_node)),
+            None => self.head = Some(new_node),
+        }
+        self.len += 1;
+    }
+}
+
+impl<T> LinkedList<T> for DoublyListNodeRc<T> {
+    fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn peek_head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.data)
+    }
+
+    fn push_head(&mut self, data: T) {
+        let node = NodeRc::new(data, Weak::new(), self.head.take());
+        match node.next_node() {
+            Some(old_head) => old_head.set_prev(Some(&node)),
+            None => self.tail = Some(Rc::clone(&node)),
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    // Паникует там, где try_pop_head вернёт ошибку
+    fn pop_head(&mut self) -> Option<T> {
+        match self.try_pop_head() {
+            Ok(data) => data,
+            Err(err) => panic!("pop_head failed: {err}"),
+        }
+    }
+
+    // Хвост известен, поэтому склейка выполняется за O(1)
+    fn join(mut self, mut other: Self) -> Self {
+        let Some(other_head) = other.head.take() else {
+            return self;
+        };
+        match self.tail.take() {
+            Some(tail) => {
+                other_head.set_prev(Some(&tail));
+                tail.set_next(Some(other_head));
+            }
+            None => self.head = Some(other_head),
+        }
+        self.tail = other.tail.take();
+        self.len += std::mem::take(&mut other.len);
+        self
+    }
+
+    fn try_divide_at(mut self, position: usize) -> Result<(Self, Self), ListError> {
+        if position > self.len {
+            return Err(self.out_of_bounds(position));
+        }
+        let Some(first) = self.node_at(position) else {
+            return Ok((self, Self::new()));
+        };
+        let Some(last) = first.prev_node() else {
+            return Ok((Self::new(), self));
+        };
+
+        last.set_next(None);
+        first.set_prev(None);
+        let second = Self {
+            head: Some(first),
+            tail: self.tail.replace(last),
+            len: self.len - position,
+        };
+        self.len = position;
+        Ok((self, second))
+    }
+
+    fn try_append_at(&mut self, position: usize, data: T) -> Result<(), ListError> {
+        if position > self.len {
+            return Err(self.out_of_bounds(position));
+        }
+        match self.node_at(position) {
+            Some(node) => self.link_before(&node, data),
+            None => self.push_tail(data),
+        }
+        Ok(())
+    }
+
+    fn try_remove_at(&mut self, position: usize) -> Result<T, ListError> {
+        if self.is_empty() {
+            return Err(ListError::Empty);
+        }
+        let node = self
+            .node_at(position)
+            .ok_or_else(|| self.out_of_bounds(position))?;
+        self.take_node(node)
+    }
+}
+
+impl<T> Drop for DoublyListNodeRc<T> {
+    fn drop(&mut self) {
+        self.tail = None;
+        let mut current = self.head.take();
+        while let Some(node) = current {
+            if let Ok(node_inner) = Rc::try_unwrap(node) {
+                current = node_inner.next.into_inner();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl<T> IntoIterator for DoublyListNodeRc<T> {
+    type Item = T;
+    type IntoIter = IntoIterRc<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterRc(self)
+    }
+}
+
+// Данные живут внутри узлов с общим владением, поэтому итерация по ссылке отдаёт сами узлы
+impl<T> IntoIterator for &DoublyListNodeRc<T> {
+    type Item = Rc<NodeRc<T>>;
+    type IntoIter = IterRc<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<T> for DoublyListNodeRc<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = DoublyListNodeRc::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for DoublyListNodeRc<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for data in iter {
+            self.push_tail(data);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a> Extend<&'a T> for DoublyListNodeRc<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T: Clone> Clone for DoublyListNodeRc<T> {
+    fn clone(&self) -> Self {
+        self.iter().map(|node| node.data.clone()).collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for DoublyListNodeRc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for DoublyListNodeRc<T> {}
+
+impl<T: PartialOrd> PartialOrd for DoublyListNodeRc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord> Ord for DoublyListNodeRc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash> Hash for DoublyListNodeRc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for node in self.iter() {
+            node.hash(state);
+        }
+        state.write_usize(self.len);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DoublyListNodeRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+fn main() {
+    let mut list = DoublyListNodeRc::new();
+    list.push_tail(2);
+    list.push_head(1);
+    list.push_tail(3);
+    let collected: Vec<_> = list.iter().rev().map(|node| node.data).collect();
+    assert_eq!(collected, vec![3, 2, 1]);
+
+    list.pop_tail();
+    list.pop_head();
+    let collected: Vec<_> = list.iter().map(|node| node.data).collect();
+    assert_eq!(collected, vec![2]);
+}
+
+fn values(list: &DoublyListNodeRc<i32>) -> Vec<i32> {
+    list.iter().map(|node| node.data).collect()
+}
+
+#[test]
+fn test_push_pop_both_ends() {
+    let mut list = DoublyListNodeRc::new();
+    list.push_tail(2);
+    list.push_head(1);
+    list.push_tail(3);
+    assert_eq!(values(&list), vec![1, 2, 3]);
+    assert_eq!(list.peek_tail(), Some(&3));
+
+    assert_eq!(list.pop_tail(), Some(3));
+    assert_eq!(list.pop_tail(), Some(2));
+    assert_eq!(list.peek_tail(), Some(&1));
+    assert_eq!(list.pop_tail(), Some(1));
+    assert_eq!(list.pop_tail(), None);
+    assert!(list.is_empty());
+
+    list.push_tail(4);
+    assert_eq!(list.peek_head(), Some(&4));
+    assert_eq!(list.pop_head(), Some(4));
+    assert_eq!(list.peek_tail(), None);
+}
+
+#[test]
+fn test_reverse_iteration() {
+    let list: DoublyListNodeRc<i32> = (1..=5).collect();
+    let reversed: Vec<_> = list.iter().rev().map(|node| node.data).collect();
+    assert_eq!(reversed, vec![5, 4, 3, 2, 1]);
+
+    let mut iter = list.iter();
+    assert_eq!(iter.next().map(|node| node.data), Some(1));
+    assert_eq!(iter.next_back().map(|node| node.data), Some(5));
+    assert_eq!(iter.len(), 3);
+    let middle: Vec<_> = iter.map(|node| node.data).collect();
+    assert_eq!(middle, vec![2, 3, 4]);
+
+    let owned: Vec<_> = list.into_iter().rev().collect();
+    assert_eq!(owned, vec![5, 4, 3, 2, 1]);
+}
+
+#[test]
+fn test_remove_by_handle() {
+    let mut list: DoublyListNodeRc<i32> = (1..=5).collect();
+    let handles: Vec<_> = list.iter().collect();
+    let [first, second, third, fourth, fifth] = handles.try_into().unwrap();
+
+    // Пока handle на узел есть ещё где-то, узел не отдаётся
+    let extra = Rc::clone(&third);
+    assert_eq!(list.remove(third), Err(ListError::Shared));
+    assert_eq!(list.remove(extra), Ok(3));
+    assert_eq!(values(&list), vec![1, 2, 4, 5]);
+
+    assert_eq!(list.remove(fifth), Ok(5));
+    assert_eq!(list.peek_tail(), Some(&4));
+    assert_eq!(list.remove(first), Ok(1));
+    assert_eq!(list.peek_head(), Some(&2));
+
+    assert_eq!(list.insert_before(&fourth, 10), Ok(()));
+    assert_eq!(values(&list), vec![2, 10, 4]);
+    let backwards: Vec<_> = list.iter().rev().map(|node| node.data).collect();
+    assert_eq!(backwards, vec![4, 10, 2]);
+
+    drop(fourth);
+    assert_eq!(list.remove(second), Ok(2));
+    assert_eq!(list.len(), 2);
+
+    // Узел другого списка не трогает этот список, в том числе после разделения
+    let other: DoublyListNodeRc<i32> = (7..=9).collect();
+    let foreign = other.node_at(1).expect("node exists");
+    assert_eq!(list.remove(Rc::clone(&foreign)), Err(ListError::StaleHandle));
+    assert_eq!(list.insert_before(&foreign, 0), Err(ListError::StaleHandle));
+    let (mut left, mut right) = other.divide_at(1).expect("divide_at failed");
+    assert_eq!(left.insert_before(&foreign, 0), Err(ListError::StaleHandle));
+    assert_eq!(right.insert_before(&foreign, 0), Ok(()));
+    assert_eq!(values(&list), vec![10, 4]);
+    assert_eq!(values(&right), vec![0, 8, 9]);
+}
+
+#[test]
+fn test_divide_and_join_keep_back_links() {
+    let list: DoublyListNodeRc<i32> = (0..6).collect();
+    let (a, b) = list.divide_at(4).expect("divide_at failed");
+    assert_eq!(a.peek_tail(), Some(&3));
+    assert_eq!(b.iter().rev().map(|node| node.data).collect::<Vec<_>>(), vec![5, 4]);
+
+    let mut joined = b.join(a);
+    assert_eq!(values(&joined), vec![4, 5, 0, 1, 2, 3]);
+    let backwards: Vec<_> = joined.iter().rev().map(|node| node.data).collect();
+    assert_eq!(backwards, vec![3, 2, 1, 0, 5, 4]);
+
+    joined.append_at(3, 9);
+    assert_eq!(joined.remove_at(2), Some(0));
+    let backwards: Vec<_> = joined.iter().rev().map(|node| node.data).collect();
+    assert_eq!(backwards, vec![3, 2, 1, 9, 5, 4]);
+}
+
+#[test]
+fn test_pop_with_live_handles() {
+    let mut list: DoublyListNodeRc<i32> = (1..=3).collect();
+    let handles: Vec<_> = list.iter().collect();
+
+    // Узлы из iter() держат оба конца, поэтому они не снимаются и список не меняется
+    assert_eq!(list.try_pop_head(), Err(ListError::Shared));
+    assert_eq!(list.try_pop_tail(), Err(ListError::Shared));
+    assert_eq!(values(&list), vec![1, 2, 3]);
+    assert_eq!(list.len(), 3);
+
+    drop(handles);
+    assert_eq!(list.try_pop_head(), Ok(Some(1)));
+    assert_eq!(list.try_pop_tail(), Ok(Some(3)));
+    assert_eq!(list.try_pop_tail(), Ok(Some(2)));
+    assert_eq!(list.try_pop_head(), Ok(None));
+    assert_eq!(list.try_pop_tail(), Ok(None));
+}
+
+// Считает живые экземпляры, чтобы проверить, что узлы действительно освобождаются
+struct DropCounter(Rc<Cell<usize>>);
+
+impl DropCounter {
+    fn new(live: &Rc<Cell<usize>>) -> Self {
+        live.set(live.get() + 1);
+        DropCounter(Rc::clone(live))
+    }
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+#[test]
+fn test_back_links_do_not_leak() {
+    let live = Rc::new(Cell::new(0));
+    let make_list = |len| (0..len).map(|_| DropCounter::new(&live)).collect::<DoublyListNodeRc<_>>();
+
+    drop(make_list(5));
+    assert_eq!(live.get(), 0);
+
+    let mut list = make_list(5);
+    list.pop_tail();
+    list.pop_head();
+    let handle = list.node_at(1).expect("node exists");
+    drop(list);
+    // Узел с внешним handle живёт вместе со своим хвостом
+    assert_eq!(live.get(), 2);
+    drop(handle);
+    assert_eq!(live.get(), 0);
+
+    let (a, b) = make_list(6).divide_at(2).expect("divide_at failed");
+    drop(b.join(a));
+    assert_eq!(live.get(), 0);
+}
+
+#[cfg(test)]
+linked_list::conformance_tests!(DoublyListNodeRc<i32>);
@@ -0,0 +1,570 @@
+// Общий интерфейс для ListNodeBox, ListNodeRc, ListNodeIndex и IndexedList
+
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fmt,
+    ops::{Bound, Range, RangeBounds},
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListError {
+    OutOfBounds { position: usize, len: usize },
+    Empty,
+    // Узел принадлежит не только этому списку
+    Shared,
+    CycleDetected,
+    // Дескриптор не указывает на элемент этого списка: элемент удалён или список другой
+    StaleHandle,
+}
+
+impl fmt::Display for ListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListError::OutOfBounds { position, len } => {
+                write!(f, "position {position} is out of bounds for list of length {len}")
+            }
+            ListError::Empty => write!(f, "list is empty"),
+            ListError::Shared => write!(f, "node is shared with another owner"),
+            ListError::CycleDetected => write!(f, "cycle detected in linked list"),
+            ListError::StaleHandle => write!(f, "handle does not refer to an element of this list"),
+        }
+    }
+}
+
+impl Error for ListError {}
+
+// Где список зацикливается: узел tail_position указывает обратно на узел entry_position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleInfo {
+    pub entry_position: usize,
+    pub cycle_len: usize,
+    pub tail_position: usize,
+}
+
+pub trait LinkedList<T>: Default + Sized {
+    fn is_empty(&self) -> bool;
+
+    // Длина хранится в списке и обновляется при каждой операции
+    fn len(&self) -> usize;
+
+    fn peek_head(&self) -> Option<&T>;
+
+    fn push_head(&mut self, data: T);
+
+    fn pop_head(&mut self) -> Option<T>;
+
+    // Элементы other идут после элементов self
+    fn join(self, other: Self) -> Self;
+
+    // OutOfBounds, если position > длины списка
+    fn try_divide_at(self, position: usize) -> Result<(Self, Self), ListError>;
+
+    // OutOfBounds, если position > длины списка
+    fn try_append_at(&mut self, position: usize, data: T) -> Result<(), ListError>;
+
+    // Empty для пустого списка, OutOfBounds, если элемента на позиции position нет
+    fn try_remove_at(&mut self, position: usize) -> Result<T, ListError>;
+
+    fn divide_at(self, position: usize) -> Option<(Self, Self)> {
+        match self.try_divide_at(position) {
+            Ok(parts) => Some(parts),
+            Err(ListError::OutOfBounds { .. }) => None,
+            Err(err) => panic!("divide_at failed: {err}"),
+        }
+    }
+
+    fn append_at(&mut self, position: usize, data: T) {
+        if let Err(err) = self.try_append_at(position, data) {
+            panic!("append_at failed: {err}");
+        }
+    }
+
+    fn remove_at(&mut self, position: usize) -> Option<T> {
+        match self.try_remove_at(position) {
+            Ok(data) => Some(data),
+            Err(ListError::OutOfBounds { .. } | ListError::Empty) => None,
+            Err(err) => panic!("remove_at failed: {err}"),
+        }
+    }
+
+    fn into_vec(mut self) -> Vec<T> {
+        let mut values = Vec::new();
+        while let Some(data) = self.pop_head() {
+            values.push(data);
+        }
+        values
+    }
+}
+
+// Переводит диапазон позиций для drain в полуинтервал start..end, паникует как Vec::drain
+pub fn resolve_range<R: RangeBounds<usize>>(range: R, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start overflows usize"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end overflows usize"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end, "range start {start} is greater than end {end}");
+    assert!(end <= len, "range end {end} is out of bounds for list of length {len}");
+    start..end
+}
+
+// Экранирует текст для строки в кавычках в DOT: подписи узлов строятся из Debug данных
+pub fn dot_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Двоичная куча голов списков для слияния k списков. BinaryHeap не принимает внешний компаратор,
+// поэтому сравнение передаётся в каждый вызов. При равенстве раньше выходит голова списка
+// с меньшим номером source, поэтому слияние устойчиво
+pub struct MergeHeap<H> {
+    entries: Vec<(H, usize)>,
+}
+
+impl<H> MergeHeap<H> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn before<F: FnMut(&H, &H) -> Ordering>(&self, a: usize, b: usize, compare: &mut F) -> bool {
+        let (a_head, a_source) = &self.entries[a];
+        let (b_head, b_source) = &self.entries[b];
+        compare(a_head, b_head).then(a_source.cmp(b_source)) == Ordering::Less
+    }
+
+    pub fn push<F: FnMut(&H, &H) -> Ordering>(&mut self, head: H, source: usize, compare: &mut F) {
+        self.entries.push((head, source));
+        let mut child = self.entries.len() - 1;
+        while child > 0 {
+            let parent = (child - 1) / 2;
+            if !self.before(child, parent, compare) {
+                break;
+            }
+            self.entries.swap(child, parent);
+            child = parent;
+        }
+    }
+
+    pub fn pop<F: FnMut(&H, &H) -> Ordering>(&mut self, compare: &mut F) -> Option<(H, usize)> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let top = self.entries.swap_remove(0);
+        let mut parent = 0;
+        loop {
+            let mut smallest = parent;
+            for child in [2 * parent + 1, 2 * parent + 2] {
+                if child < self.entries.len() && self.before(child, smallest, compare) {
+                    smallest = child;
+                }
+            }
+            if smallest == parent {
+                return Some(top);
+            }
+            self.entries.swap(parent, smallest);
+            parent = smallest;
+        }
+    }
+}
+
+// Как часто список проверяет инварианты: поиск цикла стоит O(n) на каждую операцию.
+// Значение по умолчанию выбирается фичами invariants-always, invariants-never и
+// invariants-sampled, без них проверки идут только в отладочной сборке. В релизной сборке
+// try_* тогда не замечают цикл, поэтому тесты на цикл задают Always явно
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantPolicy {
+    Always,
+    DebugOnly,
+    Never,
+    // Каждая N-я проверка, начиная с первой
+    EveryN(usize),
+}
+
+// Шаг для фичи invariants-sampled
+const SAMPLED_EVERY: usize = 64;
+
+impl Default for InvariantPolicy {
+    fn default() -> Self {
+        if cfg!(feature = "invariants-always") {
+            InvariantPolicy::Always
+        } else if cfg!(feature = "invariants-never") {
+            InvariantPolicy::Never
+        } else if cfg!(feature = "invariants-sampled") {
+            InvariantPolicy::EveryN(SAMPLED_EVERY)
+        } else {
+            InvariantPolicy::DebugOnly
+        }
+    }
+}
+
+// Политика списка вместе со счётчиком проверок. Счётчик атомарный, чтобы список оставался Sync;
+// копия списка начинает счёт заново
+#[derive(Debug)]
+pub struct InvariantChecks {
+    policy: InvariantPolicy,
+    checks: AtomicUsize,
+}
+
+impl InvariantChecks {
+    pub fn new(policy: InvariantPolicy) -> Self {
+        if let InvariantPolicy::EveryN(n) = policy {
+            assert!(n != 0, "EveryN needs a positive period");
+        }
+        Self {
+            policy,
+            checks: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn policy(&self) -> InvariantPolicy {
+        self.policy
+    }
+
+    // Вызывается перед каждой проверкой и решает, выполнять ли её
+    pub fn due(&self) -> bool {
+        match self.policy {
+            InvariantPolicy::Always => true,
+            InvariantPolicy::DebugOnly => cfg!(debug_assertions),
+            InvariantPolicy::Never => false,
+            InvariantPolicy::EveryN(n) => {
+                self.checks.fetch_add(1, AtomicOrdering::Relaxed).is_multiple_of(n)
+            }
+        }
+    }
+}
+
+impl Default for InvariantChecks {
+    fn default() -> Self {
+        Self::new(InvariantPolicy::default())
+    }
+}
+
+impl Clone for InvariantChecks {
+    fn clone(&self) -> Self {
+        Self::new(self.policy)
+    }
+}
+
+pub mod conformance {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        fmt::Debug,
+        hash::{Hash, Hasher},
+    };
+
+    use super::{LinkedList, ListError};
+
+    pub fn from_slice<L: LinkedList<i32>>(items: &[i32]) -> L {
+        let mut list = L::default();
+        for &item in items.iter().rev() {
+            list.push_head(item);
+        }
+        list
+    }
+
+    pub fn push_pop<L: LinkedList<i32>>() {
+        let mut list = L::default();
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.peek_head(), None);
+
+        list.push_head(1);
+        list.push_head(2);
+        list.push_head(3);
+        assert!(!list.is_empty());
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.peek_head(), Some(&3));
+
+        assert_eq!(list.pop_head(), Some(3));
+        assert_eq!(list.pop_head(), Some(2));
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.pop_head(), Some(1));
+        assert_eq!(list.pop_head(), None);
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+    }
+
+    pub fn push_after_pop<L: LinkedList<i32>>() {
+        let mut list: L = from_slice(&[1, 2, 3]);
+        list.pop_head();
+        list.pop_head();
+        list.push_head(4);
+        list.push_head(5);
+        assert_eq!(list.into_vec(), vec![5, 4, 3]);
+    }
+
+    pub fn join<L: LinkedList<i32>>() {
+        let a: L = from_slice(&[1, 2]);
+        let b: L = from_slice(&[3, 4, 5]);
+        let joined = a.join(b);
+        assert_eq!(joined.len(), 5);
+        assert_eq!(joined.into_vec(), vec![1, 2, 3, 4, 5]);
+
+        let a: L = from_slice(&[1, 2]);
+        assert_eq!(a.join(L::default()).into_vec(), vec![1, 2]);
+
+        let b: L = from_slice(&[3, 4]);
+        assert_eq!(L::default().join(b).into_vec(), vec![3, 4]);
+
+        assert!(L::default().join(L::default()).is_empty());
+    }
+
+    pub fn divide_at<L: LinkedList<i32>>() {
+        let list: L = from_slice(&[1, 2, 3, 4, 5]);
+        let (a, b) = list.divide_at(3).expect("divide_at failed");
+        assert_eq!((a.len(), b.len()), (3, 2));
+        assert_eq!(a.into_vec(), vec![1, 2, 3]);
+        assert_eq!(b.into_vec(), vec![4, 5]);
+
+        let list: L = from_slice(&[1, 2, 3]);
+        let (a, b) = list.divide_at(0).expect("divide_at failed");
+        assert!(a.is_empty());
+        assert_eq!(b.into_vec(), vec![1, 2, 3]);
+
+        let list: L = from_slice(&[1, 2, 3]);
+        let (a, b) = list.divide_at(3).expect("divide_at failed");
+        assert_eq!(a.into_vec(), vec![1, 2, 3]);
+        assert!(b.is_empty());
+
+        let (a, b) = L::default().divide_at(0).expect("divide_at failed");
+        assert!(a.is_empty());
+        assert!(b.is_empty());
+
+        let list: L = from_slice(&[1, 2, 3]);
+        assert!(list.divide_at(4).is_none());
+    }
+
+    pub fn append_at<L: LinkedList<i32>>() {
+        let mut list: L = from_slice(&[1, 2, 3]);
+        list.append_at(1, 9);
+        list.append_at(0, 0);
+        list.append_at(5, 10);
+        assert_eq!(list.len(), 6);
+        assert_eq!(list.into_vec(), vec![0, 1, 9, 2, 3, 10]);
+
+        let mut list = L::default();
+        list.append_at(0, 1);
+        assert_eq!(list.into_vec(), vec![1]);
+    }
+
+    pub fn append_at_out_of_bounds<L: LinkedList<i32>>() {
+        let mut list: L = from_slice(&[1, 2, 3]);
+        list.append_at(4, 9);
+    }
+
+    pub fn remove_at<L: LinkedList<i32>>() {
+        let mut list: L = from_slice(&[1, 2, 3, 4]);
+        assert_eq!(list.remove_at(3), Some(4));
+        assert_eq!(list.remove_at(1), Some(2));
+        assert_eq!(list.remove_at(0), Some(1));
+        assert_eq!(list.remove_at(1), None);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.into_vec(), vec![3]);
+
+        let mut list = L::default();
+        assert_eq!(list.remove_at(0), None);
+    }
+
+    pub fn try_errors<L: LinkedList<i32>>() {
+        let mut list: L = from_slice(&[1, 2, 3]);
+        assert_eq!(
+            list.try_append_at(4, 9),
+            Err(ListError::OutOfBounds { position: 4, len: 3 })
+        );
+        assert_eq!(list.try_append_at(3, 4), Ok(()));
+        assert_eq!(
+            list.try_remove_at(4),
+            Err(ListError::OutOfBounds { position: 4, len: 4 })
+        );
+        assert_eq!(list.try_remove_at(3), Ok(4));
+        assert_eq!(
+            list.try_divide_at(5).err(),
+            Some(ListError::OutOfBounds { position: 5, len: 3 })
+        );
+
+        let mut list = L::default();
+        assert_eq!(list.try_remove_at(0), Err(ListError::Empty));
+        assert_eq!(
+            list.try_append_at(1, 1),
+            Err(ListError::OutOfBounds { position: 1, len: 0 })
+        );
+        assert_eq!(list.try_append_at(0, 1), Ok(()));
+        assert_eq!(list.into_vec(), vec![1]);
+    }
+
+    // Последний из четырёх узлов указывает на второй. Операции, которым нужно дойти до конца
+    // списка, отказывают с CycleDetected, а не ходят по кругу и не портят длину
+    pub fn cycle_errors<L: LinkedList<i32>>(make_cycle: fn(&mut L, usize)) {
+        let cyclic = || {
+            let mut list: L = from_slice(&[1, 2, 3, 4]);
+            make_cycle(&mut list, 1);
+            list
+        };
+        assert_eq!(cyclic().try_remove_at(10), Err(ListError::CycleDetected));
+        assert_eq!(cyclic().try_remove_at(1), Err(ListError::CycleDetected));
+        assert_eq!(cyclic().try_append_at(10, 9), Err(ListError::CycleDetected));
+        assert_eq!(cyclic().try_append_at(2, 9), Err(ListError::CycleDetected));
+        assert_eq!(cyclic().try_divide_at(2).err(), Some(ListError::CycleDetected));
+        assert_eq!(cyclic().try_divide_at(10).err(), Some(ListError::CycleDetected));
+    }
+
+    fn hash_of<H: Hash>(value: &H) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn std_traits<L>()
+    where
+        L: LinkedList<i32>
+            + IntoIterator<Item = i32, IntoIter: ExactSizeIterator>
+            + FromIterator<i32>
+            + Extend<i32>
+            + for<'a> Extend<&'a i32>
+            + Clone
+            + Debug
+            + Ord
+            + Hash,
+    {
+        let mut list: L = (1..=3).collect();
+        list.extend(4..=5);
+        list.extend(&[6, 7]);
+        assert_eq!(format!("{list:?}"), "[1, 2, 3, 4, 5, 6, 7]");
+
+        assert_eq!(list.len(), 7);
+
+        let cloned = list.clone();
+        assert_eq!(list, cloned);
+        assert_eq!(hash_of(&list), hash_of(&cloned));
+
+        let mut iter = cloned.into_iter();
+        assert_eq!(iter.len(), 7);
+        iter.next();
+        assert_eq!(iter.len(), 6);
+        assert_eq!(iter.collect::<Vec<_>>(), (2..=7).collect::<Vec<_>>());
+
+        let shorter: L = (1..=6).collect();
+        let bigger: L = [1, 2, 4].into_iter().collect();
+        assert!(shorter < list);
+        assert!(list < bigger);
+        assert_eq!(list.cmp(&list.clone()), std::cmp::Ordering::Equal);
+        assert_ne!(hash_of(&list), hash_of(&shorter));
+
+        let empty: L = std::iter::empty().collect();
+        assert!(empty.is_empty());
+        assert!(empty < shorter);
+        assert_eq!(format!("{empty:?}"), "[]");
+    }
+
+    pub fn large_list<L: LinkedList<i32>>() {
+        let mut list = L::default();
+        for i in 0..10_000 {
+            list.push_head(i);
+        }
+        let (a, b) = list.divide_at(5_000).expect("divide_at failed");
+        let list = b.join(a);
+        assert_eq!(list.len(), 10_000);
+        assert_eq!(list.peek_head(), Some(&4_999));
+    }
+}
+
+// Запускает общий набор тестов для реализации LinkedList<i32>. Списки, которые умеют
+// закольцовываться, передают make_cycle и получают ещё и тесты ошибок на цикле
+#[macro_export]
+macro_rules! conformance_tests {
+    (@tests $list:ty { $($extra:tt)* }) => {
+        mod conformance {
+            use super::*;
+            use $crate::conformance;
+
+            $($extra)*
+
+            #[test]
+            fn push_pop() {
+                conformance::push_pop::<$list>();
+            }
+
+            #[test]
+            fn push_after_pop() {
+                conformance::push_after_pop::<$list>();
+            }
+
+            #[test]
+            fn join() {
+                conformance::join::<$list>();
+            }
+
+            #[test]
+            fn divide_at() {
+                conformance::divide_at::<$list>();
+            }
+
+            #[test]
+            fn append_at() {
+                conformance::append_at::<$list>();
+            }
+
+            #[test]
+            #[should_panic]
+            fn append_at_out_of_bounds() {
+                conformance::append_at_out_of_bounds::<$list>();
+            }
+
+            #[test]
+            fn remove_at() {
+                conformance::remove_at::<$list>();
+            }
+
+            #[test]
+            fn try_errors() {
+                conformance::try_errors::<$list>();
+            }
+
+            #[test]
+            fn std_traits() {
+                conformance::std_traits::<$list>();
+            }
+
+            #[test]
+            fn large_list() {
+                conformance::large_list::<$list>();
+            }
+        }
+    };
+    ($list:ty, make_cycle = $make_cycle:expr) => {
+        $crate::conformance_tests!(@tests $list {
+            // Список должен уметь with_invariant_policy: цикл ищется и в релизной сборке
+            #[test]
+            fn cycle_errors() {
+                conformance::cycle_errors::<$list>(|list, position| {
+                    let mut checked = std::mem::take(list)
+                        .with_invariant_policy($crate::InvariantPolicy::Always);
+                    $make_cycle(&mut checked, position);
+                    *list = checked;
+                });
+            }
+        });
+    };
+    ($list:ty) => {
+        $crate::conformance_tests!(@tests $list {});
+    };
+}
@@ -0,0 +1,1019 @@
+// Потокобезопасный вариант ListNodeRc: Arc вместо Rc и Mutex на каждой ссылке next вместо
+// RefCell. Операции через handle берут &self и блокируют только нужные ссылки, поэтому их можно
+// вызывать из нескольких потоков сразу; остальные операции требуют &mut, как в ListNodeRc
+#![allow(unused)]
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+};
+
+use linked_list::{CycleInfo, InvariantChecks, InvariantPolicy, LinkedList, ListError};
+
+type Link<T> = Option<Arc<NodeArc<T>>>;
+
+struct NodeArc<T> {
+    data: T,
+    next: Mutex<Link<T>>,
+}
+
+impl<T> NodeArc<T> {
+    // Ссылки блокируются только по ходу списка, от головы к хвосту, поэтому потоки не могут
+    // ждать друг друга по кругу. Запись ссылки не оставляет её в промежуточном состоянии, так что
+    // после паники в другом потоке ссылкой можно пользоваться дальше
+    fn lock_next(&self) -> MutexGuard<'_, Link<T>> {
+        self.next.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn next_node(&self) -> Link<T> {
+        self.lock_next().clone()
+    }
+
+    fn set_next(&self, next: Link<T>) {
+        *self.lock_next() = next;
+    }
+
+    fn into_parts(self) -> (T, Link<T>) {
+        let next = self.next.into_inner().unwrap_or_else(PoisonError::into_inner);
+        (self.data, next)
+    }
+}
+
+// Узлы сравниваются, хешируются и печатаются по данным, без учёта связей
+impl<T: PartialEq> PartialEq for NodeArc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl<T: Eq> Eq for NodeArc<T> {}
+
+impl<T: PartialOrd> PartialOrd for NodeArc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.data.partial_cmp(&other.data)
+    }
+}
+
+impl<T: Ord> Ord for NodeArc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.data.cmp(&other.data)
+    }
+}
+
+impl<T: Hash> Hash for NodeArc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for NodeArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.data.fmt(f)
+    }
+}
+
+// Длина атомарная: её меняют и операции через handle, которые идут параллельно
+struct ListNodeArc<T> {
+    head: Link<T>,
+    len: AtomicUsize,
+    invariants: InvariantChecks,
+}
+
+struct IntoIterArc<T>(ListNodeArc<T>);
+
+impl<T> Iterator for IntoIterArc<T> {
+    type Item = T;
+
+    // Счётчик не даёт уйти в бесконечный цикл, если список закольцован
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.len() == 0 {
+            return None;
+        }
+        let head = self.0.head.take()?;
+        match Arc::try_unwrap(head) {
+            Ok(node) => {
+                let (data, next) = node.into_parts();
+                self.0.head = next;
+                *self.0.len.get_mut() -= 1;
+                Some(data)
+            }
+            Err(_) => panic!("Multiple references to node in IntoIterArc"),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIterArc<T> {}
+
+// Длина берётся в момент создания: узлы, вставленные другими потоками во время обхода, могут
+// в него не попасть
+struct IterArc<T> {
+    next: Link<T>,
+    len: usize,
+}
+
+impl<T> Iterator for IterArc<T> {
+    type Item = Arc<NodeArc<T>>;
+
+    // Счётчик не даёт уйти в бесконечный цикл, если список закольцован
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        self.next.take().inspect(|node| {
+            self.next = node.next_node();
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterArc<T> {}
+
+// Ссылка на узел, которую можно передать в другой поток. Handle удерживает узел, поэтому
+// remove_after откажется удалять узел, на который он указывает
+struct NodeHandle<T>(Arc<NodeArc<T>>);
+
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        NodeHandle(Arc::clone(&self.0))
+    }
+}
+
+impl<T> Default for ListNodeArc<T> {
+    fn default() -> Self {
+        Self {
+            head: None,
+            len: AtomicUsize::new(0),
+            invariants: InvariantChecks::default(),
+        }
+    }
+}
+
+impl<T> ListNodeArc<T> {
+    // Проверка выполняется, только если этого требует политика списка
+    fn try_check_invariants(&self) -> Result<(), ListError> {
+        if self.invariants.due() && self.has_cycle() {
+            return Err(ListError::CycleDetected);
+        }
+        Ok(())
+    }
+
+    fn check_invariants(&self) {
+        if let Err(err) = self.try_check_invariants() {
+            match self.find_cycle() {
+                Some(info) => panic!("{err}: {info:?}"),
+                None => panic!("{err}"),
+            }
+        }
+    }
+
+    // Вызывать только для списка без цикла
+    fn out_of_bounds(&self, position: usize) -> ListError {
+        ListError::OutOfBounds {
+            position,
+            len: self.len(),
+        }
+    }
+
+    fn get_node_at(&self, position: usize) -> Option<Arc<NodeArc<T>>> {
+        self.iter().nth(position)
+    }
+
+    fn handle_at(&self, position: usize) -> Option<NodeHandle<T>> {
+        self.get_node_at(position).map(NodeHandle)
+    }
+
+    fn value<'a>(&self, handle: &'a NodeHandle<T>) -> &'a T {
+        &handle.0.data
+    }
+
+    fn next(&self, handle: &NodeHandle<T>) -> Option<NodeHandle<T>> {
+        handle.0.next_node().map(NodeHandle)
+    }
+
+    // handle должен указывать на узел этого списка; возвращает handle нового узла.
+    // Блокируется только ссылка узла handle
+    fn insert_after(&self, handle: &NodeHandle<T>, data: T) -> NodeHandle<T> {
+        self.check_invariants();
+
+        let mut link = handle.0.lock_next();
+        let node = Arc::new(NodeArc {
+            data,
+            next: Mutex::new(link.take()),
+        });
+        *link = Some(Arc::clone(&node));
+        drop(link);
+
+        self.len.fetch_add(1, AtomicOrdering::Relaxed);
+        NodeHandle(node)
+    }
+
+    // Ok(None), если за handle узлов нет; Shared, если следующий узел удерживает другой handle
+    // или обход в другом потоке. Пока ссылка узла handle заблокирована, новых ссылок на
+    // следующий узел никто получить не может, поэтому проверка счётчика не устаревает
+    fn remove_after(&self, handle: &NodeHandle<T>) -> Result<Option<T>, ListError> {
+        self.check_invariants();
+
+        let mut link = handle.0.lock_next();
+        let Some(node_to_remove) = link.take() else {
+            return Ok(None);
+        };
+        if Arc::strong_count(&node_to_remove) > 1 {
+            *link = Some(node_to_remove);
+            return Err(ListError::Shared);
+        }
+
+        *link = node_to_remove.next_node();
+        drop(link);
+        self.len.fetch_sub(1, AtomicOrdering::Relaxed);
+
+        match Arc::try_unwrap(node_to_remove) {
+            Ok(node) => Ok(Some(node.data)),
+            Err(_) => unreachable!("node was unique while its link was locked"),
+        }
+    }
+
+    fn iter(&self) -> IterArc<T> {
+        IterArc {
+            next: self.head.clone(),
+            len: self.len(),
+        }
+    }
+
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_invariant_policy(mut self, policy: InvariantPolicy) -> Self {
+        self.invariants = InvariantChecks::new(policy);
+        self
+    }
+
+    // Последний узел начинает указывать на узел с индексом position
+    fn try_make_cycle_at(&mut self, position: usize) -> Result<(), ListError> {
+        self.try_check_invariants()?;
+        if self.is_empty() {
+            return Err(ListError::Empty);
+        }
+
+        let target_node = self
+            .get_node_at(position)
+            .ok_or_else(|| self.out_of_bounds(position))?;
+
+        let last_node = self.iter().last().expect("list is not empty");
+
+        last_node.set_next(Some(target_node));
+        Ok(())
+    }
+
+    fn make_cycle_at(&mut self, position: usize) {
+        if let Err(err) = self.try_make_cycle_at(position) {
+            panic!("make_cycle_at failed: {err}");
+        }
+    }
+
+    fn has_cycle(&self) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        let mut slow_iter = self.iter_nodes();
+        let mut fast_iter = self.iter_nodes();
+
+        let mut slow = slow_iter.next();
+        let mut fast = fast_iter.next();
+
+        while slow.is_some() && fast.is_some() {
+            slow = slow_iter.next();
+
+            fast_iter.next();
+            fast = fast_iter.next();
+
+            if let (Some(s), Some(f)) = (&slow, &fast) {
+                if Arc::ptr_eq(s, f) {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    // Алгоритм Флойда, как в ListNodeRc
+    fn find_cycle(&self) -> Option<CycleInfo> {
+        let head = self.head.clone()?;
+
+        let (mut slow, mut fast) = (Arc::clone(&head), Arc::clone(&head));
+        loop {
+            slow = slow.next_node()?;
+            fast = fast.next_node()?.next_node()?;
+            if Arc::ptr_eq(&slow, &fast) {
+                break;
+            }
+        }
+
+        let mut entry = head;
+        let mut entry_position = 0;
+        while !Arc::ptr_eq(&entry, &slow) {
+            entry = entry.next_node().expect("cycle has no end");
+            slow = slow.next_node().expect("cycle has no end");
+            entry_position += 1;
+        }
+
+        let mut cycle_len = 1;
+        let mut node = entry.next_node().expect("cycle has no end");
+        while !Arc::ptr_eq(&node, &entry) {
+            node = node.next_node().expect("cycle has no end");
+            cycle_len += 1;
+        }
+
+        Some(CycleInfo {
+            entry_position,
+            cycle_len,
+            tail_position: entry_position + cycle_len - 1,
+        })
+    }
+
+    // Обрезает обратную ссылку, которую создал make_cycle_at; возвращает, где был цикл
+    fn break_cycle(&mut self) -> Option<CycleInfo> {
+        let info = self.find_cycle()?;
+        let tail = self
+            .get_node_at(info.tail_position)
+            .expect("tail is inside the list");
+        tail.set_next(None);
+        Some(info)
+    }
+
+    // Узлы держат только предыдущий узел или голова, а вход в цикл ещё и последний узел.
+    // Счётчик читается до того, как обход возьмёт ссылку на следующий узел
+    fn owns_all_nodes(&self, cycle: CycleInfo) -> bool {
+        let mut next = self.head.clone();
+        for position in 0..self.len() {
+            let Some(node) = next.take() else {
+                break;
+            };
+            let internal = if position == cycle.entry_position { 2 } else { 1 };
+            if Arc::strong_count(&node) - 1 > internal {
+                return false;
+            }
+            next = node.next_node();
+        }
+        true
+    }
+
+    // В отличие от iter, не ограничен длиной и по закольцованному списку идёт бесконечно; нужен
+    // поиску цикла и тестам
+    fn iter_nodes(&self) -> NodeIter<T> {
+        NodeIter {
+            next: self.head.clone(),
+        }
+    }
+}
+
+impl<T> LinkedList<T> for ListNodeArc<T> {
+    fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    fn len(&self) -> usize {
+        self.len.load(AtomicOrdering::Relaxed)
+    }
+
+    fn peek_head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.data)
+    }
+
+    fn pop_head(&mut self) -> Option<T> {
+        self.check_invariants();
+
+        let old_head = self.head.take()?;
+
+        self.head = old_head.next_node();
+        *self.len.get_mut() -= 1;
+
+        self.check_invariants();
+
+        match Arc::try_unwrap(old_head) {
+            Ok(node) => Some(node.data),
+            Err(_) => panic!("Cannot pop head"),
+        }
+    }
+
+    fn push_head(&mut self, data: T) {
+        self.check_invariants();
+
+        let new_head = Arc::new(NodeArc {
+            data,
+            next: Mutex::new(self.head.take()),
+        });
+
+        self.head = Some(new_head);
+        *self.len.get_mut() += 1;
+
+        self.check_invariants();
+    }
+
+    fn join(mut self, mut other: Self) -> Self {
+        self.check_invariants();
+        other.check_invariants();
+
+        if other.is_empty() {
+            return self;
+        }
+
+        if self.is_empty() {
+            return other;
+        }
+
+        if let Some(last_node) = self.iter().last() {
+            last_node.set_next(other.head.take());
+        }
+        *self.len.get_mut() += std::mem::take(other.len.get_mut());
+
+        self.check_invariants();
+        self
+    }
+
+    fn try_divide_at(mut self, position: usize) -> Result<(Self, Self), ListError> {
+        self.try_check_invariants()?;
+
+        if position == 0 {
+            return Ok((Self::new(), self));
+        }
+
+        let prev_node = self
+            .get_node_at(position - 1)
+            .ok_or_else(|| self.out_of_bounds(position))?;
+
+        let head2 = prev_node.next_node();
+        prev_node.set_next(None);
+
+        let len = self.len();
+        let list2 = ListNodeArc {
+            head: head2,
+            len: AtomicUsize::new(len - position),
+            invariants: self.invariants.clone(),
+        };
+        *self.len.get_mut() = position;
+
+        self.check_invariants();
+        Ok((self, list2))
+    }
+
+    fn try_append_at(&mut self, position: usize, data: T) -> Result<(), ListError> {
+        self.try_check_invariants()?;
+
+        if position == 0 {
+            self.push_head(data);
+            return Ok(());
+        }
+
+        let prev_node = self
+            .get_node_at(position - 1)
+            .ok_or_else(|| self.out_of_bounds(position))?;
+
+        let mut link = prev_node.lock_next();
+        let new_node = Arc::new(NodeArc {
+            data,
+            next: Mutex::new(link.take()),
+        });
+        *link = Some(new_node);
+        drop(link);
+        *self.len.get_mut() += 1;
+
+        self.check_invariants();
+        Ok(())
+    }
+
+    fn try_remove_at(&mut self, position: usize) -> Result<T, ListError> {
+        self.try_check_invariants()?;
+
+        let head = self.head.as_ref().ok_or(ListError::Empty)?;
+
+        if position == 0 {
+            if Arc::strong_count(head) > 1 {
+                return Err(ListError::Shared);
+            }
+            return self.pop_head().ok_or(ListError::Empty);
+        }
+
+        let (prev_node, node_to_remove) = self
+            .get_node_at(position - 1)
+            .and_then(|prev_node| {
+                let node = prev_node.next_node()?;
+                Some((prev_node, node))
+            })
+            .ok_or_else(|| self.out_of_bounds(position))?;
+
+        // Одна ссылка у предыдущего узла и одна локальная
+        if Arc::strong_count(&node_to_remove) > 2 {
+            return Err(ListError::Shared);
+        }
+
+        prev_node.set_next(node_to_remove.next_node());
+        *self.len.get_mut() -= 1;
+
+        self.check_invariants();
+
+        Arc::try_unwrap(node_to_remove)
+            .map(|node| node.data)
+            .map_err(|_| ListError::Shared)
+    }
+}
+
+struct NodeIter<T> {
+    next: Link<T>,
+}
+
+impl<T> Iterator for NodeIter<T> {
+    type Item = Arc<NodeArc<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.take().inspect(|node| {
+            self.next = node.next_node();
+        })
+    }
+}
+
+impl<T> Drop for ListNodeArc<T> {
+    fn drop(&mut self) {
+        // Узлы цикла держат друг друга, поэтому обратная ссылка обрезается до освобождения. Если
+        // часть узлов держит handle, цикл остаётся целым для него
+        if let Some(info) = self.find_cycle() {
+            if self.owns_all_nodes(info) {
+                self.break_cycle();
+            }
+        }
+
+        let mut current = self.head.take();
+        while let Some(node) = current {
+            if let Ok(node_inner) = Arc::try_unwrap(node) {
+                current = node_inner.into_parts().1;
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl<T> IntoIterator for ListNodeArc<T> {
+    type Item = T;
+    type IntoIter = IntoIterArc<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterArc(self)
+    }
+}
+
+// Данные живут внутри узлов с общим владением, поэтому итерация по ссылке отдаёт сами узлы
+impl<T> IntoIterator for &ListNodeArc<T> {
+    type Item = Arc<NodeArc<T>>;
+    type IntoIter = IterArc<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<T> for ListNodeArc<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = ListNodeArc::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: Clone> Clone for ListNodeArc<T> {
+    fn clone(&self) -> Self {
+        let mut list: Self = self.iter().map(|node| node.data.clone()).collect();
+        list.invariants = self.invariants.clone();
+        list
+    }
+}
+
+impl<T: PartialEq> PartialEq for ListNodeArc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for ListNodeArc<T> {}
+
+impl<T: PartialOrd> PartialOrd for ListNodeArc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord> Ord for ListNodeArc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash> Hash for ListNodeArc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for node in self.iter() {
+            node.hash(state);
+        }
+        state.write_usize(self.len());
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ListNodeArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+fn main() {
+    let mut list = ListNodeArc::new();
+    list.push_head(1);
+    list.push_head(2);
+    list.push_head(3);
+    let collected1: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(collected1, vec![3, 2, 1]);
+
+    list.pop_head();
+    list.pop_head();
+    let collected2: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(collected2, vec![1]);
+}
+
+// проверка отсутствия переполнения стека
+fn create_and_drop_large_list() {
+    let mut list = ListNodeArc::new();
+    list.extend(0..10_000);
+}
+
+// Элементы добавляются в конец списка, как у коллекций std
+impl<T> Extend<T> for ListNodeArc<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.check_invariants();
+
+        let mut last = self.iter().last();
+        for data in iter {
+            let node = Arc::new(NodeArc {
+                data,
+                next: Mutex::new(None),
+            });
+            match &last {
+                Some(last_node) => last_node.set_next(Some(Arc::clone(&node))),
+                None => self.head = Some(Arc::clone(&node)),
+            }
+            *self.len.get_mut() += 1;
+            last = Some(node);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a> Extend<&'a T> for ListNodeArc<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+#[test]
+fn test_large_list_drop() {
+    create_and_drop_large_list();
+}
+
+#[test]
+fn test_list_is_send_and_sync() {
+    fn assert_send_sync<L: Send + Sync>() {}
+    assert_send_sync::<ListNodeArc<String>>();
+    assert_send_sync::<NodeHandle<String>>();
+    assert_send_sync::<IterArc<String>>();
+}
+
+#[test]
+fn test_join_and_divide_at() {
+    let a: ListNodeArc<i32> = (1..=2).collect();
+    let b: ListNodeArc<i32> = (3..=5).collect();
+    let joined = a.join(b);
+    assert_eq!(joined.len(), 5);
+
+    let (left, right) = joined.divide_at(3).expect("divide_at failed");
+    let left_values: Vec<_> = left.iter_nodes().map(|node| node.data).collect();
+    let right_values: Vec<_> = right.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(left_values, vec![1, 2, 3]);
+    assert_eq!(right_values, vec![4, 5]);
+    assert_eq!((left.len(), right.len()), (3, 2));
+}
+
+#[test]
+fn test_append_and_remove_at() {
+    let mut list: ListNodeArc<i32> = (1..=3).collect();
+    list.append_at(1, 9);
+    assert_eq!(list.remove_at(3), Some(3));
+    let values: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(values, vec![1, 9, 2]);
+
+    let handle = list.get_node_at(1).expect("node exists");
+    assert_eq!(list.try_remove_at(1), Err(ListError::Shared));
+    drop(handle);
+    assert_eq!(list.try_remove_at(1), Ok(9));
+    assert_eq!(
+        list.try_append_at(5, 0),
+        Err(ListError::OutOfBounds { position: 5, len: 2 })
+    );
+}
+
+#[test]
+fn test_find_and_break_cycle() {
+    for len in 1..6 {
+        for position in 0..len {
+            let mut list = (0..len)
+                .collect::<ListNodeArc<usize>>()
+                .with_invariant_policy(InvariantPolicy::Always);
+            assert!(!list.has_cycle());
+
+            list.make_cycle_at(position);
+            assert!(list.has_cycle());
+            let info = CycleInfo {
+                entry_position: position,
+                cycle_len: len - position,
+                tail_position: len - 1,
+            };
+            assert_eq!(list.find_cycle(), Some(info));
+            assert_eq!(list.try_append_at(0, 9), Err(ListError::CycleDetected));
+
+            assert_eq!(list.break_cycle(), Some(info));
+            let values: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+            assert_eq!(values, (0..len).collect::<Vec<_>>());
+        }
+    }
+}
+
+// Без проверок инвариантов join и extend всё равно доходят только до узла len - 1
+#[test]
+fn test_join_and_extend_cyclic_without_checks() {
+    let with_never =
+        || (0..4).collect::<ListNodeArc<i32>>().with_invariant_policy(InvariantPolicy::Never);
+
+    let mut list = with_never();
+    list.make_cycle_at(1);
+    list.extend([4, 5]);
+    assert!(list.find_cycle().is_none());
+    let values: Vec<_> = list.iter().map(|node| node.data).collect();
+    assert_eq!(values, vec![0, 1, 2, 3, 4, 5]);
+
+    let mut list = with_never();
+    list.make_cycle_at(2);
+    let joined = list.join(with_never());
+    assert!(joined.find_cycle().is_none());
+    let values: Vec<_> = joined.iter().map(|node| node.data).collect();
+    assert_eq!(values, vec![0, 1, 2, 3, 0, 1, 2, 3]);
+}
+
+// Считает живые экземпляры, чтобы проверить, что узлы действительно освобождаются
+struct DropCounter(Arc<AtomicUsize>);
+
+impl DropCounter {
+    fn new(live: &Arc<AtomicUsize>) -> Self {
+        live.fetch_add(1, AtomicOrdering::SeqCst);
+        DropCounter(Arc::clone(live))
+    }
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, AtomicOrdering::SeqCst);
+    }
+}
+
+#[test]
+fn test_drop_frees_all_nodes() {
+    let live = Arc::new(AtomicUsize::new(0));
+    let make_list = |len| {
+        (0..len)
+            .map(|_| DropCounter::new(&live))
+            .collect::<ListNodeArc<_>>()
+    };
+
+    drop(make_list(5));
+    assert_eq!(live.load(AtomicOrdering::SeqCst), 0);
+
+    for position in 0..5 {
+        let mut list = make_list(5);
+        list.make_cycle_at(position);
+        drop(list);
+        assert_eq!(live.load(AtomicOrdering::SeqCst), 0, "cycle at {position}");
+    }
+
+    // Handle в другом потоке держит узел и хвост за ним после удаления списка
+    let list = make_list(5);
+    let handle = list.handle_at(2).expect("node exists");
+    drop(list);
+    assert_eq!(live.load(AtomicOrdering::SeqCst), 3);
+    std::thread::spawn(move || drop(handle))
+        .join()
+        .expect("thread panicked");
+    assert_eq!(live.load(AtomicOrdering::SeqCst), 0);
+
+    // Handle на хвосте цикла держит весь цикл; начало списка до цикла освобождается
+    let mut list = make_list(5);
+    list.make_cycle_at(1);
+    let tail = list.handle_at(4).expect("node exists");
+    drop(list);
+    assert_eq!(live.load(AtomicOrdering::SeqCst), 4);
+    let entry = tail.0.next_node().expect("cycle is kept");
+    assert_eq!(Arc::strong_count(&entry), 2);
+    drop(entry);
+    tail.0.set_next(None);
+    assert_eq!(live.load(AtomicOrdering::SeqCst), 1);
+    drop(tail);
+    assert_eq!(live.load(AtomicOrdering::SeqCst), 0);
+}
+
+#[test]
+fn test_node_handles() {
+    let list: ListNodeArc<&str> = ["parse", "emit"].into_iter().collect();
+    let parse = list.handle_at(0).expect("node exists");
+
+    let check = list.insert_after(&parse, "check");
+    let lower = list.insert_after(&check, "lower");
+    assert_eq!(list.value(&lower), &"lower");
+    assert_eq!(list.next(&lower).map(|next| *list.value(&next)), Some("emit"));
+    assert_eq!(list.len(), 4);
+
+    // На lower есть handle, поэтому удалить его нельзя
+    assert_eq!(list.remove_after(&check), Err(ListError::Shared));
+    drop(lower);
+    assert_eq!(list.remove_after(&check), Ok(Some("lower")));
+    assert_eq!(list.remove_after(&check), Ok(Some("emit")));
+    assert_eq!(list.remove_after(&check), Ok(None));
+    assert_eq!(list.len(), 2);
+    let values: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(values, vec!["parse", "check"]);
+}
+
+#[test]
+fn test_for_loops() {
+    let list: ListNodeArc<i32> = (1..=3).collect();
+
+    let mut seen = Vec::new();
+    for node in &list {
+        seen.push(node.data);
+    }
+    assert_eq!(seen, vec![1, 2, 3]);
+
+    let owned: Vec<_> = list.into_iter().collect();
+    assert_eq!(owned, vec![1, 2, 3]);
+}
+
+// Запускает потоки и ждёт каждый не дольше минуты: зависание означало бы взаимную блокировку.
+// Паника в потоке пробрасывается в тест
+fn run_threads<F: FnOnce() + Send + 'static>(workers: Vec<F>) {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::mpsc,
+        thread,
+        time::Duration,
+    };
+
+    let (done, finished) = mpsc::channel();
+    let threads: Vec<_> = workers
+        .into_iter()
+        .map(|work| {
+            let done = done.clone();
+            thread::spawn(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(work));
+                // Получатель мог уже сдаться по таймауту
+                done.send(()).ok();
+                if let Err(payload) = result {
+                    panic::resume_unwind(payload);
+                }
+            })
+        })
+        .collect();
+
+    for _ in 0..threads.len() {
+        if finished.recv_timeout(Duration::from_secs(60)).is_err() {
+            panic!("threads did not finish in time, probably a deadlock");
+        }
+    }
+    for thread in threads {
+        if let Err(payload) = thread.join() {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+#[test]
+fn test_concurrent_push_pop() {
+    const THREADS: usize = 8;
+    const OPS: usize = 500;
+
+    let list = Arc::new(Mutex::new(ListNodeArc::new()));
+    let popped = Arc::new(Mutex::new(Vec::new()));
+    let workers = (0..THREADS)
+        .map(|thread| {
+            let list = Arc::clone(&list);
+            let popped = Arc::clone(&popped);
+            move || {
+                for op in 0..OPS {
+                    list.lock().unwrap().push_head(thread * OPS + op);
+                    // Каждый поток снимает не больше, чем положил, поэтому список не пуст
+                    if op % 3 != 0 {
+                        let value = list.lock().unwrap().pop_head().expect("list is not empty");
+                        popped.lock().unwrap().push(value);
+                    }
+                }
+            }
+        })
+        .collect();
+    run_threads(workers);
+
+    let list = Arc::into_inner(list).expect("threads are done").into_inner().unwrap();
+    assert!(!list.has_cycle());
+    let mut values = Arc::into_inner(popped).expect("threads are done").into_inner().unwrap();
+    assert_eq!(list.len() + values.len(), THREADS * OPS);
+    values.extend(list);
+    values.sort_unstable();
+    assert_eq!(values, (0..THREADS * OPS).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_concurrent_handles() {
+    const THREADS: usize = 6;
+    const INSERTS: usize = 300;
+    const REMOVES: usize = 100;
+
+    // Поток thread работает только после своего узла (thread, 0), читатели обходят весь список
+    let list: ListNodeArc<(usize, usize)> = (0..THREADS).map(|thread| (thread, 0)).collect();
+    let list = Arc::new(list.with_invariant_policy(InvariantPolicy::EveryN(16)));
+
+    // Handle берутся заранее: после первых вставок позиция уже не указывает на узел-якорь
+    let anchors: Vec<_> = (0..THREADS)
+        .map(|thread| list.handle_at(thread).expect("anchor exists"))
+        .collect();
+
+    let mut workers: Vec<Box<dyn FnOnce() + Send>> = Vec::new();
+    for (thread, anchor) in anchors.into_iter().enumerate() {
+        let list = Arc::clone(&list);
+        workers.push(Box::new(move || {
+            for op in 1..=INSERTS {
+                list.insert_after(&anchor, (thread, op));
+            }
+            for op in (INSERTS - REMOVES + 1..=INSERTS).rev() {
+                // Узел может ненадолго держать обход читателя
+                let removed = loop {
+                    match list.remove_after(&anchor) {
+                        Err(ListError::Shared) => std::thread::yield_now(),
+                        result => break result,
+                    }
+                };
+                assert_eq!(removed, Ok(Some((thread, op))));
+            }
+        }));
+    }
+    for _ in 0..2 {
+        let list = Arc::clone(&list);
+        workers.push(Box::new(move || {
+            for _ in 0..50 {
+                // Узлы одного потока идут подряд, от новых к старым
+                let mut last: Option<(usize, usize)> = None;
+                for node in list.iter() {
+                    let (thread, op) = node.data;
+                    if let Some((last_thread, last_op)) = last {
+                        if op != 0 {
+                            assert_eq!(thread, last_thread);
+                            assert!(last_op == 0 || op < last_op);
+                        }
+                    }
+                    last = Some((thread, op));
+                }
+            }
+        }));
+    }
+    run_threads(workers);
+
+    let list = Arc::into_inner(list).expect("threads are done");
+    assert!(!list.has_cycle());
+    assert_eq!(list.len(), THREADS * (1 + INSERTS - REMOVES));
+    let expected: Vec<_> = (0..THREADS)
+        .flat_map(|thread| {
+            let ops = (1..=INSERTS - REMOVES).rev().map(move |op| (thread, op));
+            std::iter::once((thread, 0)).chain(ops)
+        })
+        .collect();
+    let values: Vec<_> = list.into_iter().collect();
+    assert_eq!(values, expected);
+}
+
+#[cfg(test)]
+linked_list::conformance_tests!(ListNodeArc<i32>, make_cycle = ListNodeArc::make_cycle_at);
@@ -1,6 +1,20 @@
 #![allow(unused)]
 
-type Link<T> = Option<Box<NodeBox<T>>>;
+use std::{
+    cell::Cell,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    ops::RangeBounds,
+    ptr::NonNull,
+};
+
+use linked_list::{resolve_range, LinkedList, ListError, MergeHeap};
+
+// Узлы выделяются через Box, но связи хранятся сырыми указателями: держать указатель
+// на хвост рядом с цепочкой Box нельзя, Box требует уникальности при каждом перемещении (Miri)
+type Link<T> = Option<NonNull<NodeBox<T>>>;
 
 struct NodeBox<T> {
     data: T,
@@ -8,9 +22,23 @@ struct NodeBox<T> {
 }
 
 impl<T> NodeBox<T> {
+    fn alloc(data: T, next: Link<T>) -> NonNull<NodeBox<T>> {
+        NonNull::from(Box::leak(Box::new(NodeBox { data, next })))
+    }
+
+    // SAFETY: node выделен через alloc, отвязан от списка и больше не используется
+    unsafe fn free(node: NonNull<NodeBox<T>>) -> NodeBox<T> {
+        unsafe { *Box::from_raw(node.as_ptr()) }
+    }
+
     fn set_next(&mut self, next: Link<T>) {
         self.next = next;
     }
+
+    fn next_node(&self) -> Option<&NodeBox<T>> {
+        // SAFETY: следующий узел живёт не меньше текущего
+        self.next.map(|next| unsafe { &*next.as_ptr() })
+    }
 }
 
 struct IntoIterBox<T>(ListNodeBox<T>);
@@ -21,10 +49,17 @@ impl<T> Iterator for IntoIterBox<T> {
     fn next(&mut self) -> Option<Self::Item> {
         self.0.pop_head()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
 }
 
+impl<T> ExactSizeIterator for IntoIterBox<T> {}
+
 struct IterBox<'a, T> {
     next: Option<&'a NodeBox<T>>,
+    len: usize,
 }
 
 impl<'a, T> Iterator for IterBox<'a, T> {
@@ -32,14 +67,22 @@ impl<'a, T> Iterator for IterBox<'a, T> {
     
     fn next(&mut self) -> Option<Self::Item> {
         self.next.take().map(|node| {
-            self.next = node.next.as_deref();
+            self.next = node.next_node();
+            self.len -= 1;
             &node.data
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
 }
 
+impl<T> ExactSizeIterator for IterBox<'_, T> {}
+
 struct IterMutBox<'a, T> {
-    next: Option<&'a mut Box<NodeBox<T>>>,
+    next: Option<&'a mut NodeBox<T>>,
+    len: usize,
 }
 
 impl<'a, T> Iterator for IterMutBox<'a, T> {
@@ -47,26 +90,318 @@ impl<'a, T> Iterator for IterMutBox<'a, T> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let current = self.next.take()?;
-        self.next = current.next.as_mut();
+        // SAFETY: список заимствован на 'a, каждый узел выдаётся один раз
+        self.next = current.next.map(|next| unsafe { &mut *next.as_ptr() });
+        self.len -= 1;
         Some(&mut current.data)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMutBox<'_, T> {}
+
+// Курсор помнит узел перед текущим, поэтому все правки в его позиции выполняются за O(1).
+// Когда текущего узла нет, курсор стоит за последним элементом.
+struct CursorMut<'a, T> {
+    list: &'a mut ListNodeBox<T>,
+    prev: Link<T>,
+    index: usize,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    fn current_link(&self) -> Link<T> {
+        match self.prev {
+            // SAFETY: prev — узел списка, который заимствован курсором
+            Some(prev) => unsafe { prev.as_ref().next },
+            None => self.list.head,
+        }
+    }
+
+    fn set_current_link(&mut self, link: Link<T>) {
+        match self.prev {
+            // SAFETY: prev — узел списка, который заимствован курсором
+            Some(mut prev) => unsafe { prev.as_mut().next = link },
+            None => self.list.head = link,
+        }
+    }
+
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current_link().is_none()
+    }
+
+    fn current(&mut self) -> Option<&mut T> {
+        // SAFETY: узел живёт, пока список заимствован курсором
+        self.current_link()
+            .map(|current| unsafe { &mut (*current.as_ptr()).data })
+    }
+
+    fn peek_next(&mut self) -> Option<&mut T> {
+        let current = self.current_link()?;
+        // SAFETY: узлы живут, пока список заимствован курсором
+        unsafe {
+            (*current.as_ptr())
+                .next
+                .map(|next| &mut (*next.as_ptr()).data)
+        }
+    }
+
+    fn move_next(&mut self) -> bool {
+        match self.current_link() {
+            Some(current) => {
+                self.prev = Some(current);
+                self.index += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Новый элемент становится перед текущим, курсор остаётся на текущем
+    fn insert_before(&mut self, data: T) {
+        let current = self.current_link();
+        let node = NodeBox::alloc(data, current);
+        self.set_current_link(Some(node));
+        if current.is_none() {
+            self.list.tail = Some(node);
+        }
+        self.list.len += 1;
+        self.prev = Some(node);
+        self.index += 1;
+    }
+
+    // В конце списка работает как insert_before
+    fn insert_after(&mut self, data: T) {
+        let Some(mut current) = self.current_link() else {
+            return self.insert_before(data);
+        };
+
+        // SAFETY: current — узел списка, который заимствован курсором
+        unsafe {
+            let node = NodeBox::alloc(data, current.as_ref().next);
+            current.as_mut().next = Some(node);
+            if self.list.tail == Some(current) {
+                self.list.tail = Some(node);
+            }
+        }
+        self.list.len += 1;
+    }
+
+    // Курсор переходит на следующий элемент
+    fn remove_current(&mut self) -> Option<T> {
+        let current = self.current_link()?;
+        // SAFETY: узел сразу отвязывается от списка
+        let node = unsafe { NodeBox::free(current) };
+        self.set_current_link(node.next);
+        if node.next.is_none() {
+            self.list.tail = self.prev;
+        }
+        self.list.len -= 1;
+        Some(node.data)
+    }
+
+    // Отрезает текущий элемент и все следующие за ним
+    fn split_off(&mut self) -> ListNodeBox<T> {
+        let Some(current) = self.current_link() else {
+            return ListNodeBox::new();
+        };
+
+        self.set_current_link(None);
+        let rest_len = self.list.len - self.index;
+        let rest = ListNodeBox::from_links(Some(current), self.list.tail, rest_len);
+        self.list.tail = self.prev;
+        self.list.len = self.index;
+        rest
+    }
+
+    // Отрезает все элементы после текущего
+    fn split_after(&mut self) -> ListNodeBox<T> {
+        let Some(mut current) = self.current_link() else {
+            return ListNodeBox::new();
+        };
+
+        // SAFETY: current — узел списка, который заимствован курсором
+        let next = unsafe { current.as_mut().next.take() };
+        if next.is_none() {
+            return ListNodeBox::new();
+        }
+
+        let rest_len = self.list.len - self.index - 1;
+        let rest = ListNodeBox::from_links(next, self.list.tail, rest_len);
+        self.list.tail = Some(current);
+        self.list.len = self.index + 1;
+        rest
+    }
+
+    // Элементы other встают перед текущим, курсор остаётся на текущем
+    fn splice_before(&mut self, mut other: ListNodeBox<T>) {
+        let count = std::mem::take(&mut other.len);
+        let (Some(other_head), Some(mut other_tail)) = (other.head.take(), other.tail.take())
+        else {
+            return;
+        };
+
+        let current = self.current_link();
+        // SAFETY: other_tail — живой узел, other больше им не владеет
+        unsafe { other_tail.as_mut().next = current };
+        self.set_current_link(Some(other_head));
+        if current.is_none() {
+            self.list.tail = Some(other_tail);
+        }
+        self.list.len += count;
+        self.prev = Some(other_tail);
+        self.index += count;
+    }
+
+    // Элементы other встают сразу после текущего
+    fn splice_after(&mut self, mut other: ListNodeBox<T>) {
+        let Some(mut current) = self.current_link() else {
+            return self.splice_before(other);
+        };
+        let (Some(other_head), Some(mut other_tail)) = (other.head.take(), other.tail.take())
+        else {
+            return;
+        };
+
+        // SAFETY: current — узел списка, other_tail — узел, которым other больше не владеет
+        unsafe {
+            other_tail.as_mut().next = current.as_ref().next;
+            current.as_mut().next = Some(other_head);
+        }
+        if self.list.tail == Some(current) {
+            self.list.tail = Some(other_tail);
+        }
+        self.list.len += std::mem::take(&mut other.len);
+    }
+}
+
+// Отвязывает подходящие узлы по мере обхода; то, что не пройдено, остаётся в списке
+struct ExtractIfBox<'a, T, F: FnMut(&mut T) -> bool> {
+    cursor: CursorMut<'a, T>,
+    pred: F,
+}
+
+impl<T, F: FnMut(&mut T) -> bool> Iterator for ExtractIfBox<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(data) = self.cursor.current() {
+            if (self.pred)(data) {
+                return self.cursor.remove_current();
+            }
+            self.cursor.move_next();
+        }
+        None
+    }
+}
+
+// Недочитанные элементы диапазона удаляются при drop, как у Vec::drain
+struct DrainBox<'a, T> {
+    cursor: CursorMut<'a, T>,
+    remaining: usize,
+}
+
+impl<T> Iterator for DrainBox<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.cursor.remove_current()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for DrainBox<'_, T> {}
+
+impl<T> Drop for DrainBox<'_, T> {
+    fn drop(&mut self) {
+        for _ in self.by_ref() {}
+    }
+}
+
+// Цепочки, которые сортировка или слияние ещё не вернули в список. Если compare паникует,
+// Drop дописывает их в конец списка: порядок тогда не определён, но узлы не теряются и длина
+// остаётся верной
+struct PendingChains<'a, T> {
+    list: &'a mut ListNodeBox<T>,
+    left: Link<T>,
+    right: Link<T>,
+    rest: Link<T>,
+}
+
+impl<T> PendingChains<'_, T> {
+    // Сливает left и right в конец списка
+    fn merge<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: &mut F) {
+        loop {
+            // SAFETY: обе цепочки состоят из узлов, которыми владеет этот список
+            let take_right = match (self.left, self.right) {
+                (Some(l), Some(r)) => unsafe {
+                    // При равенстве берётся левый узел, поэтому сортировка устойчива
+                    compare(&r.as_ref().data, &l.as_ref().data) == Ordering::Less
+                },
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (None, None) => return,
+            };
+
+            let side = if take_right { &mut self.right } else { &mut self.left };
+            let node = side.expect("side checked above");
+            *side = unsafe { node.as_ref().next };
+            self.list.push_tail_node(node);
+        }
+    }
+}
+
+impl<T> Drop for PendingChains<'_, T> {
+    fn drop(&mut self) {
+        for link in [self.left.take(), self.right.take(), self.rest.take()] {
+            self.list.push_chain(link);
+        }
+    }
 }
 
 struct ListNodeBox<T> {
     head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _marker: PhantomData<Box<NodeBox<T>>>,
 }
 
+// Список владеет узлами так же, как владел бы цепочкой Box
+unsafe impl<T: Send> Send for ListNodeBox<T> {}
+unsafe impl<T: Sync> Sync for ListNodeBox<T> {}
+
 impl<T> Default for ListNodeBox<T> {
     fn default() -> Self {
-        Self {
-            head: Default::default(),
-        }
+        Self::from_links(None, None, 0)
     }
 }
 
 impl<T> ListNodeBox<T> {
-    fn is_empty(&self) -> bool {
-        self.head.is_none()
+    fn from_links(head: Link<T>, tail: Link<T>, len: usize) -> Self {
+        Self {
+            head,
+            tail,
+            len,
+            _marker: PhantomData,
+        }
+    }
+
+    fn head_node(&self) -> Option<&NodeBox<T>> {
+        // SAFETY: узлы живут, пока жив список
+        self.head.map(|head| unsafe { &*head.as_ptr() })
     }
 
     fn get_node_at(&self, position: usize) -> Option<&NodeBox<T>> {
@@ -77,126 +412,487 @@ impl<T> ListNodeBox<T> {
         Self::default()
     }
 
-    fn into_iter(self) -> IntoIterBox<T> {
-        IntoIterBox(self)
-    }
-    
     fn iter(&self) -> IterBox<'_, T> {
         IterBox {
-            next: self.head.as_deref(),
+            next: self.head_node(),
+            len: self.len,
         }
     }
     
     fn iter_mut(&mut self) -> IterMutBox<'_, T> {
         IterMutBox {
-            next: self.head.as_mut(),
+            // SAFETY: список заимствован мутабельно на всё время итерации
+            next: self.head.map(|head| unsafe { &mut *head.as_ptr() }),
+            len: self.len,
         }
     }
 
-    fn push_head(&mut self, data: T) {
-        self.head = Some(Box::new(NodeBox {
-            data,
-            next: self.head.take(),
-        }))
+    fn iter_nodes(&self) -> NodeIter<'_, T> {
+        NodeIter {
+            next: self.head_node(),
+        }
     }
 
-    fn pop_head(&mut self) -> Option<T> {
-        self.head.take().map(|node| {
-            self.head = node.next;
-            node.data
-        })
+    fn peek_tail(&self) -> Option<&T> {
+        // SAFETY: узлы живут, пока жив список
+        self.tail.map(|tail| unsafe { &(*tail.as_ptr()).data })
     }
 
-    fn join(mut self, mut other: Self) -> Self {
-        if other.is_empty() {
-            return self;
+    fn push_tail(&mut self, data: T) {
+        self.push_tail_node(NodeBox::alloc(data, None));
+        self.len += 1;
+    }
+
+    fn push_tail_node(&mut self, mut node: NonNull<NodeBox<T>>) {
+        // SAFETY: node и tail — узлы, которыми владеет этот список
+        unsafe { node.as_mut().next = None };
+        match self.tail {
+            Some(mut tail) => unsafe { tail.as_mut().next = Some(node) },
+            None => self.head = Some(node),
+        }
+        self.tail = Some(node);
+    }
+
+    // Список односвязный, поэтому узел перед хвостом ищется проходом от головы
+    fn pop_tail(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        if self.head == Some(tail) {
+            return self.pop_head();
         }
 
-        if self.is_empty() {
-            return other;
+        let mut prev = self.head.expect("non-empty list has head");
+        // SAFETY: все узлы от головы до хвоста принадлежат списку
+        unsafe {
+            while prev.as_ref().next != Some(tail) {
+                prev = prev.as_ref().next.expect("tail is reachable from head");
+            }
+            prev.as_mut().next = None;
         }
+        self.tail = Some(prev);
+        self.len -= 1;
+
+        // SAFETY: хвост только что отвязан
+        Some(unsafe { NodeBox::free(tail) }.data)
+    }
+
+    // Переносит все элементы other в конец списка за O(1)
+    fn append(&mut self, other: &mut Self) {
+        let Some(other_head) = other.head.take() else {
+            return;
+        };
 
-        let mut current = &mut self.head;
-        while current.as_ref().expect("join error").next.is_some() {
-            current = &mut current.as_mut().expect("join error").next;
+        match self.tail {
+            // SAFETY: tail — последний узел этого списка
+            Some(mut tail) => unsafe { tail.as_mut().next = Some(other_head) },
+            None => self.head = Some(other_head),
         }
-        if let Some(last_node) = current.as_mut() {
-            last_node.next = other.head.take();
+        self.tail = other.tail.take();
+        self.len += std::mem::take(&mut other.len);
+    }
+
+    fn split_off(&mut self, at: usize) -> Self {
+        match self.cursor_at(at) {
+            Ok(mut cursor) => cursor.split_off(),
+            Err(err) => panic!("split_off failed: {err}"),
         }
+    }
 
-        self
+    fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_mut(|data| f(data));
     }
 
-    fn divide_at(&mut self, position: usize) -> Option<(Self, Self)> {
-        if self.is_empty() {
-            return None;
+    fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        self.extract_if(|data| !f(data)).for_each(drop);
+    }
+
+    fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, pred: F) -> ExtractIfBox<'_, T, F> {
+        ExtractIfBox {
+            cursor: self.cursor_mut(),
+            pred,
         }
+    }
 
-        if position == 0 {
-            return Some((Self::new(), std::mem::take(self)));
+    fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> DrainBox<'_, T> {
+        let range = resolve_range(range, self.len);
+        let cursor = match self.cursor_at(range.start) {
+            Ok(cursor) => cursor,
+            Err(err) => unreachable!("range is checked: {err}"),
+        };
+        DrainBox {
+            cursor,
+            remaining: range.len(),
         }
+    }
+
+    fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
 
-        let mut current = &mut self.head;
-        for _ in 0..position {
-            match current.as_mut() {
-                Some(node) => current = &mut node.next,
-                None => return None,
+    // Восходящая сортировка слиянием: узлы только перевязываются, рекурсии нет
+    fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        let len = self.len;
+        let mut width = 1;
+        while width < len {
+            let rest = self.take_chain();
+            let mut pending = PendingChains {
+                list: self,
+                left: None,
+                right: None,
+                rest,
+            };
+            while pending.rest.is_some() {
+                pending.left = pending.rest.take();
+                pending.right = Self::cut_after(pending.left, width);
+                pending.rest = Self::cut_after(pending.right, width);
+                pending.merge(&mut compare);
             }
+            width *= 2;
         }
-        let head2 = current.take();
+    }
 
-        let list1 = ListNodeBox {
-            head: self.head.take(),
-        };
+    // Отрезает первые n узлов цепочки и возвращает оставшуюся часть
+    fn cut_after(link: Link<T>, n: usize) -> Link<T> {
+        let mut current = link?;
+        // SAFETY: цепочка состоит из узлов, которыми владеет сортируемый список
+        unsafe {
+            for _ in 1..n {
+                current = current.as_ref().next?;
+            }
+            current.as_mut().next.take()
+        }
+    }
 
-        let list2 = ListNodeBox { head: head2 };
+    fn merge_sorted(a: Self, b: Self) -> Self
+    where
+        T: Ord,
+    {
+        Self::merge_sorted_by(a, b, T::cmp)
+    }
 
-        Some((list1, list2))
+    fn merge_sorted_by_key<K: Ord, F: FnMut(&T) -> K>(a: Self, b: Self, mut f: F) -> Self {
+        Self::merge_sorted_by(a, b, |x, y| f(x).cmp(&f(y)))
     }
 
-    fn append_at(&mut self, position: usize, data: T) {
-        if position == 0 {
-            self.push_head(data);
-            return;
+    // Узлы a и b перевязываются в один список; при равенстве первым идёт элемент из a
+    fn merge_sorted_by<F: FnMut(&T, &T) -> Ordering>(mut a: Self, mut b: Self, mut compare: F) -> Self {
+        let mut merged = Self::new();
+        merged.len = std::mem::take(&mut a.len) + std::mem::take(&mut b.len);
+        PendingChains {
+            left: a.take_chain(),
+            right: b.take_chain(),
+            rest: None,
+            list: &mut merged,
         }
+        .merge(&mut compare);
+        merged
+    }
 
-        let mut current = &mut self.head;
-        for _ in 0..position {
-            current = &mut current.as_mut().expect("append_at error").next;
+    fn merge_k_sorted(lists: Vec<Self>) -> Self
+    where
+        T: Ord,
+    {
+        Self::merge_k_sorted_by(lists, T::cmp)
+    }
+
+    fn merge_k_sorted_by_key<K: Ord, F: FnMut(&T) -> K>(lists: Vec<Self>, mut f: F) -> Self {
+        Self::merge_k_sorted_by(lists, |x, y| f(x).cmp(&f(y)))
+    }
+
+    // O(N log k): в куче лежит по одной голове от каждого списка. При равенстве первым идёт
+    // элемент из списка, который стоит в lists раньше
+    fn merge_k_sorted_by<F: FnMut(&T, &T) -> Ordering>(lists: Vec<Self>, mut compare: F) -> Self {
+        // SAFETY: в куче лежат только узлы, которыми владеет сливаемый список
+        let mut compare_nodes = |a: &NonNull<NodeBox<T>>, b: &NonNull<NodeBox<T>>| unsafe {
+            compare(&a.as_ref().data, &b.as_ref().data)
+        };
+
+        let mut merged = Self::new();
+        let mut heap = MergeHeap::with_capacity(lists.len());
+        for (source, mut list) in lists.into_iter().enumerate() {
+            merged.len += std::mem::take(&mut list.len);
+            if let Some(head) = list.take_chain() {
+                heap.push(head, source, &mut compare_nodes);
+            }
         }
 
-        let old_next = current.take();
-        *current = Some(Box::new(NodeBox {
-            data,
-            next: old_next,
-        }));
+        while let Some((node, source)) = heap.pop(&mut compare_nodes) {
+            // SAFETY: node — узел, которым теперь владеет merged
+            if let Some(next) = unsafe { node.as_ref().next } {
+                heap.push(next, source, &mut compare_nodes);
+            }
+            merged.push_tail_node(node);
+        }
+        merged
     }
 
-    fn remove_at(&mut self, position: usize) {
-        if self.is_empty() {
+    // Забирает все узлы списка как цепочку; длину вызывающий переносит сам
+    fn take_chain(&mut self) -> Link<T> {
+        self.tail = None;
+        self.head.take()
+    }
+
+    fn reverse(&mut self) {
+        self.tail = self.head;
+        self.head = Self::reverse_chain(self.head.take());
+    }
+
+    fn rotate_left(&mut self, k: usize) {
+        if self.len == 0 || k.is_multiple_of(self.len) {
             return;
         }
+        let first = self.head.take();
+        self.tail = None;
+        let second = Self::cut_after(first, k % self.len);
+        self.push_chain(second);
+        self.push_chain(first);
+    }
 
-        if position == 0 {
-            self.pop_head();
+    fn rotate_right(&mut self, k: usize) {
+        if self.len == 0 {
             return;
         }
+        self.rotate_left(self.len - k % self.len);
+    }
 
-        let mut current = &mut self.head;
-        for _ in 0..position {
-            current = &mut current.as_mut().expect("remove_at error").next;
+    // Разворачивает каждую полную группу из k узлов, неполный остаток не трогается
+    fn reverse_k_groups(&mut self, k: usize) {
+        assert_ne!(k, 0, "group size must be positive");
+        let mut rest = self.head.take();
+        self.tail = None;
+        for _ in 0..self.len / k {
+            let group = rest;
+            rest = Self::cut_after(group, k);
+            self.push_chain(Self::reverse_chain(group));
         }
+        self.push_chain(rest);
+    }
 
-        if let Some(node) = current.as_mut() {
-            *current = node.next.take();
+    // L0, Ln, L1, Ln-1, ...: вторая половина разворачивается и вплетается в первую
+    fn reorder(&mut self) {
+        let mut first = self.head.take();
+        self.tail = None;
+        let mut second = Self::reverse_chain(Self::cut_after(first, self.len.div_ceil(2)));
+        while let Some(node) = first {
+            // SAFETY: обе половины состоят из узлов, которыми владеет этот список
+            first = unsafe { node.as_ref().next };
+            self.push_tail_node(node);
+            if let Some(node) = second {
+                second = unsafe { node.as_ref().next };
+                self.push_tail_node(node);
+            }
         }
     }
 
-    fn iter_nodes(&self) -> NodeIter<'_, T> {
-        NodeIter {
-            next: self.head.as_deref(),
+    // Сначала элементы с чётными позициями, затем с нечётными, порядок внутри групп сохраняется
+    fn odd_even(&mut self) {
+        let mut rest = self.head.take();
+        self.tail = None;
+        let mut odd = Self::new();
+        let mut to_odd = false;
+        while let Some(node) = rest {
+            // SAFETY: цепочка состоит из узлов, которыми владеет этот список
+            rest = unsafe { node.as_ref().next };
+            if to_odd {
+                odd.push_tail_node(node);
+            } else {
+                self.push_tail_node(node);
+            }
+            to_odd = !to_odd;
+        }
+        // push_tail_node не меняет len, поэтому длины обоих списков остаются верными
+        self.append(&mut odd);
+    }
+
+    // Медленный указатель идёт на шаг, быстрый на два; возвращает узел перед серединой и середину.
+    // При чётной длине серединой считается первый узел второй половины
+    fn middle_links(&self) -> (Link<T>, Link<T>) {
+        let (mut prev, mut slow, mut fast) = (None, self.head, self.head);
+        // SAFETY: все узлы от головы до хвоста принадлежат списку
+        unsafe {
+            while let Some(next) = fast.and_then(|fast| fast.as_ref().next) {
+                fast = next.as_ref().next;
+                prev = slow;
+                slow = slow.and_then(|slow| slow.as_ref().next);
+            }
+        }
+        (prev, slow)
+    }
+
+    fn middle(&self) -> Option<&T> {
+        let (_, middle) = self.middle_links();
+        // SAFETY: узел принадлежит списку и живёт, пока список заимствован
+        middle.map(|middle| unsafe { &(*middle.as_ptr()).data })
+    }
+
+    // nth_from_end(0) — последний элемент; ведущий итератор уходит на k + 1 элементов вперёд
+    fn nth_from_end(&self, k: usize) -> Option<&T> {
+        let mut lead = self.iter();
+        lead.nth(k)?;
+        let mut trail = self.iter();
+        let mut current = trail.next();
+        for _ in lead {
+            current = trail.next();
+        }
+        current
+    }
+
+    // Вторая половина временно разворачивается и сравнивается с первой, затем восстанавливается
+    fn is_palindrome(&mut self) -> bool
+    where
+        T: PartialEq,
+    {
+        let (_, middle) = self.middle_links();
+        let reversed = Self::reverse_chain(middle);
+
+        let (mut front, mut back) = (self.head, reversed);
+        let mut result = true;
+        for _ in 0..self.len / 2 {
+            let (Some(f), Some(b)) = (front, back) else {
+                break;
+            };
+            // SAFETY: обе цепочки состоят из узлов, которыми владеет этот список
+            unsafe {
+                if f.as_ref().data != b.as_ref().data {
+                    result = false;
+                    break;
+                }
+                front = f.as_ref().next;
+                back = b.as_ref().next;
+            }
+        }
+
+        // Узел перед серединой по-прежнему указывает на середину, она снова станет началом цепочки
+        Self::reverse_chain(reversed);
+        result
+    }
+
+    // Вторая половина начинается с middle(), при нечётной длине она длиннее на один элемент
+    fn split_halves(mut self) -> (Self, Self) {
+        let (prev, middle) = self.middle_links();
+        let Some(mut prev) = prev else {
+            return (Self::new(), self);
+        };
+        // SAFETY: prev — узел этого списка
+        unsafe { prev.as_mut().next = None };
+
+        let second = Self::from_links(middle, self.tail, self.len - self.len / 2);
+        self.tail = Some(prev);
+        self.len /= 2;
+        (self, second)
+    }
+
+    fn reverse_chain(mut link: Link<T>) -> Link<T> {
+        let mut reversed = None;
+        while let Some(mut node) = link {
+            // SAFETY: цепочка состоит из узлов, которыми владеет этот список
+            unsafe {
+                link = node.as_ref().next;
+                node.as_mut().next = reversed;
+            }
+            reversed = Some(node);
+        }
+        reversed
+    }
+
+    fn push_chain(&mut self, mut link: Link<T>) {
+        while let Some(node) = link {
+            // SAFETY: цепочка состоит из узлов, которыми владеет этот список
+            link = unsafe { node.as_ref().next };
+            self.push_tail_node(node);
         }
     }
+
+    fn cursor_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            list: self,
+            prev: None,
+            index: 0,
+        }
+    }
+
+    // Курсор на элементе position; при position == len курсор стоит за последним элементом
+    fn cursor_at(&mut self, position: usize) -> Result<CursorMut<'_, T>, ListError> {
+        let mut cursor = self.cursor_mut();
+        while cursor.index() < position {
+            if !cursor.move_next() {
+                return Err(ListError::OutOfBounds {
+                    position,
+                    len: cursor.index(),
+                });
+            }
+        }
+        Ok(cursor)
+    }
+}
+
+impl<T> LinkedList<T> for ListNodeBox<T> {
+    fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn peek_head(&self) -> Option<&T> {
+        self.head_node().map(|node| &node.data)
+    }
+
+    fn push_head(&mut self, data: T) {
+        let node = NodeBox::alloc(data, self.head);
+        if self.tail.is_none() {
+            self.tail = Some(node);
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    fn pop_head(&mut self) -> Option<T> {
+        let head = self.head?;
+        // SAFETY: голова сразу отвязывается от списка
+        let node = unsafe { NodeBox::free(head) };
+        self.head = node.next;
+        if self.head.is_none() {
+            self.tail = None;
+        }
+        self.len -= 1;
+        Some(node.data)
+    }
+
+    fn join(mut self, mut other: Self) -> Self {
+        self.append(&mut other);
+        self
+    }
+
+    fn try_divide_at(mut self, position: usize) -> Result<(Self, Self), ListError> {
+        let rest = self.cursor_at(position)?.split_off();
+        Ok((self, rest))
+    }
+
+    fn try_append_at(&mut self, position: usize, data: T) -> Result<(), ListError> {
+        self.cursor_at(position)?.insert_before(data);
+        Ok(())
+    }
+
+    fn try_remove_at(&mut self, position: usize) -> Result<T, ListError> {
+        if self.is_empty() {
+            return Err(ListError::Empty);
+        }
+
+        self.cursor_at(position)?
+            .remove_current()
+            .ok_or(ListError::OutOfBounds {
+                position,
+                len: position,
+            })
+    }
 }
 
 struct NodeIter<'a, T> {
@@ -207,12 +903,113 @@ impl<'a, T> Iterator for NodeIter<'a, T> {
     type Item = &'a NodeBox<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.next.take().map(|node| {
-            self.next = node.next.as_deref();
-            node
+        self.next.take().inspect(|node| {
+            self.next = node.next_node();
         })
     }
 }
+
+// Все обходы итеративные: рекурсивный drop/clone по цепочке узлов переполняет стек на длинных списках
+impl<T> Drop for ListNodeBox<T> {
+    fn drop(&mut self) {
+        while self.pop_head().is_some() {}
+    }
+}
+
+impl<T: Clone> Clone for ListNodeBox<T> {
+    fn clone(&self) -> Self {
+        let mut list = Self::new();
+        for data in self.iter() {
+            list.push_tail(data.clone());
+        }
+        list
+    }
+}
+
+impl<T> IntoIterator for ListNodeBox<T> {
+    type Item = T;
+    type IntoIter = IntoIterBox<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterBox(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ListNodeBox<T> {
+    type Item = &'a T;
+    type IntoIter = IterBox<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ListNodeBox<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMutBox<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> FromIterator<T> for ListNodeBox<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = ListNodeBox::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for ListNodeBox<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push_tail(item);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a> Extend<&'a T> for ListNodeBox<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T: PartialEq> PartialEq for ListNodeBox<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for ListNodeBox<T> {}
+
+impl<T: PartialOrd> PartialOrd for ListNodeBox<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord> Ord for ListNodeBox<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash> Hash for ListNodeBox<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for data in self.iter() {
+            data.hash(state);
+        }
+        state.write_usize(self.len);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ListNodeBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 fn main() {
     let mut list = ListNodeBox::new();
     list.push_head(1);
@@ -227,6 +1024,59 @@ fn main() {
     assert_eq!(collected2, vec![1]);
 }
 
+// проверка отсутствия переполнения стека
+fn create_large_list() -> ListNodeBox<i32> {
+    let mut list = ListNodeBox::new();
+    for i in 0..1_000_000 {
+        list.push_head(i);
+    }
+    list
+}
+
+fn create_and_drop_large_list() {
+    create_large_list();
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_large_list_drop() {
+    create_and_drop_large_list();
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_large_list_clone() {
+    let list = create_large_list();
+    let cloned = list.clone();
+    assert_eq!(list, cloned);
+    assert_eq!(cloned.peek_head(), Some(&999_999));
+}
+
+#[test]
+fn test_eq_hash_debug() {
+    use std::hash::DefaultHasher;
+
+    fn hash_of(list: &ListNodeBox<i32>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        list.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let mut a = ListNodeBox::new();
+    a.push_head(2);
+    a.push_head(1);
+    let mut b = a.clone();
+
+    assert_eq!(a, b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+    assert_eq!(format!("{a:?}"), "[1, 2]");
+
+    b.push_head(0);
+    assert_ne!(a, b);
+    assert_ne!(hash_of(&a), hash_of(&b));
+    assert_eq!(format!("{b:?}"), "[0, 1, 2]");
+}
+
 #[test]
 fn test_join() {
     let mut a = ListNodeBox::new();
@@ -283,3 +1133,610 @@ fn test_remove_at() {
     let collected: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
     assert_eq!(collected, vec![4, 3, 2]);
 }
+
+#[cfg(test)]
+linked_list::conformance_tests!(ListNodeBox<i32>);
+
+#[test]
+fn test_cursor_move_and_edit() {
+    let mut list: ListNodeBox<i32> = ListNodeBox::new();
+    list.push_head(3);
+    list.push_head(2);
+    list.push_head(1);
+
+    let mut cursor = list.cursor_mut();
+    assert_eq!(cursor.current(), Some(&mut 1));
+    assert_eq!(cursor.peek_next(), Some(&mut 2));
+
+    assert!(cursor.move_next());
+    assert_eq!(cursor.index(), 1);
+    cursor.insert_before(10);
+    cursor.insert_after(20);
+    assert_eq!(cursor.index(), 2);
+    assert_eq!(cursor.current(), Some(&mut 2));
+
+    *cursor.current().expect("cursor on element") = 5;
+    assert!(cursor.move_next());
+    assert_eq!(cursor.remove_current(), Some(20));
+    assert_eq!(cursor.current(), Some(&mut 3));
+
+    assert!(cursor.move_next());
+    assert!(cursor.is_at_end());
+    assert!(!cursor.move_next());
+    assert_eq!(cursor.remove_current(), None);
+    cursor.insert_after(4);
+    assert!(cursor.is_at_end());
+
+    let collected: Vec<_> = list.iter().copied().collect();
+    assert_eq!(collected, vec![1, 10, 5, 3, 4]);
+}
+
+#[test]
+fn test_cursor_split_and_splice() {
+    let mut list: ListNodeBox<i32> = ListNodeBox::new();
+    for i in (1..=5).rev() {
+        list.push_head(i);
+    }
+
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    let after = cursor.split_after();
+    let tail = cursor.split_off();
+    assert!(cursor.is_at_end());
+
+    let mut other = ListNodeBox::new();
+    other.push_head(8);
+    other.push_head(7);
+    cursor.splice_before(other);
+    assert!(cursor.is_at_end());
+    assert_eq!(cursor.index(), 3);
+
+    let collected: Vec<_> = list.iter().copied().collect();
+    assert_eq!(collected, vec![1, 7, 8]);
+    assert_eq!(tail.iter().copied().collect::<Vec<_>>(), vec![2]);
+    assert_eq!(after.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+    let mut cursor = list.cursor_mut();
+    cursor.splice_after(after);
+    cursor.move_next();
+    cursor.splice_before(tail);
+    assert_eq!(cursor.current(), Some(&mut 3));
+
+    let collected: Vec<_> = list.iter().copied().collect();
+    assert_eq!(collected, vec![1, 2, 3, 4, 5, 7, 8]);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_cursor_local_edits() {
+    let mut list = ListNodeBox::new();
+    let mut model = Vec::new();
+
+    let mut cursor = list.cursor_mut();
+    for i in 0..10_000 {
+        cursor.insert_before(i);
+        model.push(i);
+        if i % 3 == 0 {
+            cursor.insert_after(-i);
+            cursor.move_next();
+            model.push(-i);
+        }
+    }
+
+    let mut cursor = list.cursor_mut();
+    let mut position = 0;
+    while cursor.current().is_some() {
+        if cursor.current().is_some_and(|data| *data < 0) {
+            cursor.remove_current();
+            model.remove(position);
+        } else {
+            cursor.move_next();
+            position += 1;
+        }
+    }
+
+    let collected: Vec<_> = list.iter().copied().collect();
+    assert_eq!(collected, model);
+}
+
+fn values(list: &ListNodeBox<i32>) -> Vec<i32> {
+    list.iter().copied().collect()
+}
+
+#[test]
+fn test_push_tail_pop_tail() {
+    let mut list = ListNodeBox::new();
+    list.push_tail(1);
+    list.push_tail(2);
+    list.push_head(0);
+    list.push_tail(3);
+    assert_eq!(values(&list), vec![0, 1, 2, 3]);
+    assert_eq!(list.peek_tail(), Some(&3));
+
+    assert_eq!(list.pop_tail(), Some(3));
+    assert_eq!(list.pop_head(), Some(0));
+    assert_eq!(list.pop_tail(), Some(2));
+    assert_eq!(list.pop_tail(), Some(1));
+    assert_eq!(list.pop_tail(), None);
+    assert_eq!(list.peek_tail(), None);
+
+    list.push_tail(4);
+    assert_eq!(list.peek_head(), Some(&4));
+    assert_eq!(list.peek_tail(), Some(&4));
+}
+
+#[test]
+fn test_fifo_queue() {
+    let mut queue = ListNodeBox::new();
+    let mut popped = Vec::new();
+    for i in 0..100 {
+        queue.push_tail(i);
+        if i % 3 == 2 {
+            popped.extend(queue.pop_head());
+        }
+    }
+    popped.extend(queue);
+    assert_eq!(popped, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_append_keeps_tail() {
+    let mut a = ListNodeBox::new();
+    a.push_tail(1);
+    a.push_tail(2);
+    let mut b = ListNodeBox::new();
+    b.push_tail(3);
+
+    a.append(&mut b);
+    assert!(b.is_empty());
+    assert_eq!(b.peek_tail(), None);
+    a.push_tail(4);
+    b.push_tail(5);
+    assert_eq!(values(&a), vec![1, 2, 3, 4]);
+    assert_eq!(values(&b), vec![5]);
+
+    let mut empty = ListNodeBox::new();
+    empty.append(&mut a);
+    empty.push_tail(6);
+    assert_eq!(values(&empty), vec![1, 2, 3, 4, 6]);
+}
+
+#[test]
+fn test_split_off_keeps_tails() {
+    for at in 0..=4 {
+        let mut left = ListNodeBox::new();
+        for i in 0..4 {
+            left.push_tail(i);
+        }
+        let mut right = left.split_off(at);
+        left.push_tail(10);
+        right.push_tail(20);
+
+        let mut expected_left: Vec<_> = (0..at as i32).collect();
+        expected_left.push(10);
+        let mut expected_right: Vec<_> = (at as i32..4).collect();
+        expected_right.push(20);
+        assert_eq!(values(&left), expected_left);
+        assert_eq!(values(&right), expected_right);
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_split_off_out_of_bounds() {
+    let mut list = ListNodeBox::new();
+    list.push_tail(1);
+    list.split_off(2);
+}
+
+#[test]
+fn test_cursor_keeps_tail() {
+    let mut list = ListNodeBox::new();
+    list.push_tail(1);
+    list.push_tail(2);
+
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    assert_eq!(cursor.remove_current(), Some(2));
+    cursor.insert_before(3);
+    list.push_tail(4);
+    assert_eq!(values(&list), vec![1, 3, 4]);
+
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.move_next();
+    cursor.insert_after(5);
+    let mut other = ListNodeBox::new();
+    other.push_tail(6);
+    cursor.move_next();
+    cursor.splice_after(other);
+    list.push_tail(7);
+    assert_eq!(values(&list), vec![1, 3, 4, 5, 6, 7]);
+
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    let rest = cursor.split_after();
+    list.push_tail(8);
+    assert_eq!(values(&list), vec![1, 3, 8]);
+    assert_eq!(rest.peek_tail(), Some(&7));
+}
+
+#[test]
+fn test_sort() {
+    let mut list = ListNodeBox::new();
+    for x in [5, 3, 9, 1, 3, 7, 0, 2] {
+        list.push_tail(x);
+    }
+    list.sort();
+    assert_eq!(values(&list), vec![0, 1, 2, 3, 3, 5, 7, 9]);
+    list.push_tail(10);
+    assert_eq!(list.peek_tail(), Some(&10));
+
+    let mut empty: ListNodeBox<i32> = ListNodeBox::new();
+    empty.sort();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_sort_is_stable() {
+    let mut list = ListNodeBox::new();
+    let mut model = Vec::new();
+    for i in 0..100 {
+        let item = ((i * 37) % 10, i);
+        list.push_tail(item);
+        model.push(item);
+    }
+
+    list.sort_by_key(|&(key, _)| key);
+    model.sort_by_key(|&(key, _)| key);
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), model);
+
+    list.sort_by(|a, b| b.1.cmp(&a.1));
+    model.sort_by_key(|&(_, i)| std::cmp::Reverse(i));
+    assert_eq!(list.iter().copied().collect::<Vec<_>>(), model);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_sort_large_list() {
+    let mut list = ListNodeBox::new();
+    for i in 0..1_000_000u64 {
+        list.push_tail(i.wrapping_mul(2_654_435_761) % 1_000_003);
+    }
+    list.sort();
+    assert!(list.iter().zip(list.iter().skip(1)).all(|(a, b)| a <= b));
+}
+
+#[test]
+fn test_for_loops() {
+    let mut list: ListNodeBox<i32> = (1..=3).collect();
+    for data in &mut list {
+        *data *= 10;
+    }
+
+    let mut seen = Vec::new();
+    for data in &list {
+        seen.push(*data);
+    }
+    assert_eq!(seen, vec![10, 20, 30]);
+
+    let owned: Vec<_> = list.into_iter().collect();
+    assert_eq!(owned, vec![10, 20, 30]);
+}
+
+// Сравнивает операцию над списком с той же операцией над Vec для длин 0..12
+fn check_against_model(op: impl Fn(&mut ListNodeBox<i32>), model: impl Fn(&mut Vec<i32>)) {
+    for len in 0..12 {
+        let mut list: ListNodeBox<i32> = (0..len).collect();
+        let mut expected: Vec<i32> = (0..len).collect();
+        op(&mut list);
+        model(&mut expected);
+        assert_eq!(values(&list), expected, "len = {len}");
+        assert_eq!(list.len(), expected.len());
+        assert_eq!(list.peek_tail(), expected.last());
+        list.push_tail(100);
+        assert_eq!(list.peek_tail(), Some(&100));
+    }
+}
+
+#[test]
+fn test_reverse() {
+    check_against_model(|list| list.reverse(), |v| v.reverse());
+}
+
+#[test]
+fn test_rotate() {
+    for k in 0..15 {
+        check_against_model(
+            |list| list.rotate_left(k),
+            |v| {
+                if !v.is_empty() {
+                    let k = k % v.len();
+                    v.rotate_left(k);
+                }
+            },
+        );
+        check_against_model(
+            |list| list.rotate_right(k),
+            |v| {
+                if !v.is_empty() {
+                    let k = k % v.len();
+                    v.rotate_right(k);
+                }
+            },
+        );
+    }
+}
+
+#[test]
+fn test_reverse_k_groups() {
+    for k in 1..6 {
+        check_against_model(
+            |list| list.reverse_k_groups(k),
+            |v| v.chunks_exact_mut(k).for_each(|group| group.reverse()),
+        );
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_reverse_zero_groups() {
+    let mut list: ListNodeBox<i32> = (0..3).collect();
+    list.reverse_k_groups(0);
+}
+
+#[test]
+fn test_reorder() {
+    check_against_model(
+        |list| list.reorder(),
+        |v| {
+            let mut reordered = Vec::new();
+            let (mut i, mut j) = (0, v.len());
+            while i < j {
+                reordered.push(v[i]);
+                i += 1;
+                if i < j {
+                    j -= 1;
+                    reordered.push(v[j]);
+                }
+            }
+            *v = reordered;
+        },
+    );
+}
+
+#[test]
+fn test_odd_even() {
+    check_against_model(
+        |list| list.odd_even(),
+        |v| {
+            let odd_even = v.iter().step_by(2).chain(v.iter().skip(1).step_by(2));
+            *v = odd_even.copied().collect();
+        },
+    );
+}
+
+#[test]
+fn test_middle_and_nth_from_end() {
+    for len in 0..10 {
+        let list: ListNodeBox<i32> = (0..len).collect();
+        assert_eq!(list.middle(), (len > 0).then_some(&(len / 2)));
+        for k in 0..12 {
+            let expected = (k < len).then(|| len - 1 - k);
+            assert_eq!(list.nth_from_end(k as usize).copied(), expected);
+        }
+    }
+}
+
+#[test]
+fn test_is_palindrome() {
+    let cases: [&[i32]; 7] = [&[], &[1], &[1, 1], &[1, 2], &[1, 2, 1], &[1, 2, 2, 1], &[1, 2, 3, 1]];
+    for case in cases {
+        let mut list: ListNodeBox<i32> = case.iter().copied().collect();
+        let expected = case.iter().eq(case.iter().rev());
+        assert_eq!(list.is_palindrome(), expected, "{case:?}");
+        // Список восстановлен после временного разворота
+        assert_eq!(values(&list), case);
+        assert_eq!(list.peek_tail(), case.last());
+    }
+}
+
+#[test]
+fn test_split_halves() {
+    for len in 0..10 {
+        let list: ListNodeBox<i32> = (0..len).collect();
+        let (mut first, second) = list.split_halves();
+        assert_eq!(values(&first), (0..len / 2).collect::<Vec<_>>());
+        assert_eq!(values(&second), (len / 2..len).collect::<Vec<_>>());
+        assert_eq!((first.len(), second.len()), (len as usize / 2, (len - len / 2) as usize));
+        assert_eq!(second.peek_tail().copied(), (0..len).last());
+
+        first.push_tail(100);
+        assert_eq!(first.peek_tail(), Some(&100));
+    }
+}
+
+#[test]
+fn test_merge_sorted() {
+    let a: ListNodeBox<i32> = [1, 3, 5, 7].into_iter().collect();
+    let b: ListNodeBox<i32> = [2, 3, 8].into_iter().collect();
+    let mut merged = ListNodeBox::merge_sorted(a, b);
+    assert_eq!(values(&merged), vec![1, 2, 3, 3, 5, 7, 8]);
+    assert_eq!(merged.len(), 7);
+    merged.push_tail(9);
+    assert_eq!(merged.peek_tail(), Some(&9));
+
+    let empty = ListNodeBox::<i32>::merge_sorted(ListNodeBox::new(), ListNodeBox::new());
+    assert!(empty.is_empty());
+
+    // При равных ключах элементы из первого списка идут раньше
+    let a: ListNodeBox<(i32, char)> = [(1, 'a'), (2, 'a'), (2, 'b')].into_iter().collect();
+    let b: ListNodeBox<(i32, char)> = [(1, 'c'), (2, 'c')].into_iter().collect();
+    let merged = ListNodeBox::merge_sorted_by_key(a, b, |&(key, _)| key);
+    let tags: Vec<_> = merged.iter().map(|&(_, tag)| tag).collect();
+    assert_eq!(tags, vec!['a', 'c', 'a', 'b', 'c']);
+}
+
+// Считает живые экземпляры, чтобы проверить, что узлы не теряются
+struct DropCounter<'a>(&'a Cell<usize>);
+
+impl<'a> DropCounter<'a> {
+    fn new(live: &'a Cell<usize>, key: i32) -> (i32, Self) {
+        live.set(live.get() + 1);
+        (key, DropCounter(live))
+    }
+}
+
+impl Drop for DropCounter<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+#[test]
+fn test_panicking_compare_keeps_nodes() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let live = Cell::new(0);
+    let make_list = |keys: &[i32]| {
+        keys.iter()
+            .map(|&key| DropCounter::new(&live, key))
+            .collect::<ListNodeBox<_>>()
+    };
+    let panicking = |limit: usize| {
+        let mut calls = 0;
+        move |a: &(i32, DropCounter), b: &(i32, DropCounter)| {
+            calls += 1;
+            assert!(calls < limit, "compare gave up");
+            a.0.cmp(&b.0)
+        }
+    };
+
+    let a = make_list(&[1, 3, 5, 7]);
+    let b = make_list(&[2, 4, 6]);
+    let merge = || ListNodeBox::merge_sorted_by(a, b, panicking(3));
+    let result = catch_unwind(AssertUnwindSafe(merge));
+    assert!(result.is_err());
+    assert_eq!(live.get(), 0);
+
+    // После паники сортировки все элементы остаются в списке, длина верная
+    let mut list = make_list(&[5, 1, 4, 2, 3, 0, 6]);
+    let result = catch_unwind(AssertUnwindSafe(|| list.sort_by(panicking(6))));
+    assert!(result.is_err());
+    assert_eq!(list.len(), 7);
+    assert_eq!(list.iter().count(), 7);
+    let mut keys: Vec<_> = list.iter().map(|(key, _)| *key).collect();
+    keys.sort_unstable();
+    assert_eq!(keys, (0..7).collect::<Vec<_>>());
+    list.push_tail(DropCounter::new(&live, 7));
+    assert_eq!(list.peek_tail().map(|(key, _)| *key), Some(7));
+    drop(list);
+    assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn test_merge_k_sorted() {
+    let mut lists = Vec::new();
+    let mut model = Vec::new();
+    for source in 0..7 {
+        let mut items: Vec<_> = (0..source * 3).map(|i| ((i * 7 + source) % 10, source)).collect();
+        items.sort_by_key(|&(key, _)| key);
+        model.extend(items.iter().copied());
+        lists.push(items.into_iter().collect::<ListNodeBox<_>>());
+    }
+    // Устойчивость: при равных ключах порядок как у конкатенации списков
+    model.sort_by_key(|&(key, _)| key);
+
+    let mut merged = ListNodeBox::merge_k_sorted_by_key(lists, |&(key, _)| key);
+    assert_eq!(merged.iter().copied().collect::<Vec<_>>(), model);
+    assert_eq!(merged.len(), model.len());
+    merged.push_tail((100, 0));
+    assert_eq!(merged.peek_tail(), Some(&(100, 0)));
+
+    let lists: Vec<ListNodeBox<i32>> = vec![ListNodeBox::new(), (0..3).collect(), ListNodeBox::new()];
+    assert_eq!(values(&ListNodeBox::merge_k_sorted(lists)), vec![0, 1, 2]);
+    assert!(ListNodeBox::<i32>::merge_k_sorted(Vec::new()).is_empty());
+}
+
+#[test]
+fn test_retain_and_extract_if() {
+    let mut list: ListNodeBox<i32> = (1..=10).collect();
+    list.retain(|&data| data % 3 != 0);
+    assert_eq!(values(&list), vec![1, 2, 4, 5, 7, 8, 10]);
+
+    list.retain_mut(|data| {
+        *data *= 10;
+        *data != 40
+    });
+    assert_eq!(values(&list), vec![10, 20, 50, 70, 80, 100]);
+    assert_eq!(list.peek_tail(), Some(&100));
+
+    let big: Vec<_> = list.extract_if(|data| *data > 60).collect();
+    assert_eq!(big, vec![70, 80, 100]);
+    assert_eq!(list.len(), 3);
+    list.push_tail(1);
+    assert_eq!(values(&list), vec![10, 20, 50, 1]);
+
+    // Непройденная часть списка не трогается
+    assert_eq!(list.extract_if(|_| true).next(), Some(10));
+    assert_eq!(values(&list), vec![20, 50, 1]);
+
+    list.retain(|_| false);
+    assert!(list.is_empty());
+    assert_eq!(list.peek_tail(), None);
+}
+
+#[test]
+fn test_drain() {
+    let mut list: ListNodeBox<i32> = (0..8).collect();
+    let drained: Vec<_> = list.drain(2..5).collect();
+    assert_eq!(drained, vec![2, 3, 4]);
+    assert_eq!(values(&list), vec![0, 1, 5, 6, 7]);
+
+    let mut drain = list.drain(3..);
+    assert_eq!(drain.len(), 2);
+    assert_eq!(drain.next(), Some(6));
+    drop(drain);
+    assert_eq!(values(&list), vec![0, 1, 5]);
+    list.push_tail(9);
+    assert_eq!(list.peek_tail(), Some(&9));
+
+    assert_eq!(list.drain(..).count(), 4);
+    assert!(list.is_empty());
+    assert_eq!(list.drain(..).count(), 0);
+}
+
+#[test]
+#[should_panic]
+fn test_drain_out_of_bounds() {
+    let mut list: ListNodeBox<i32> = (0..3).collect();
+    list.drain(1..4);
+}
+
+#[test]
+fn test_len_after_cursor_ops() {
+    let mut list: ListNodeBox<i32> = (1..=5).collect();
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    cursor.insert_before(10);
+    cursor.insert_after(20);
+    cursor.remove_current();
+    cursor.splice_before((6..=8).collect());
+    assert_eq!(list.len(), 9);
+    assert_eq!(list.iter().len(), 9);
+
+    let mut cursor = list.cursor_at(3).expect("position 3 is in bounds");
+    let rest = cursor.split_after();
+    assert_eq!((list.len(), rest.len()), (4, 5));
+
+    let mut cursor = list.cursor_at(1).expect("position 1 is in bounds");
+    let rest = cursor.split_off();
+    assert_eq!((list.len(), rest.len()), (1, 3));
+
+    list.append(&mut rest.into_iter().collect());
+    list.pop_tail();
+    let mut iter = list.iter_mut();
+    iter.next();
+    assert_eq!(iter.len(), 2);
+    assert_eq!(list.len(), 3);
+}
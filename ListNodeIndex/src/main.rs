@@ -1,15 +1,64 @@
 // Индексированный список
 #![allow(unused)]
 
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    ops::RangeBounds,
+    sync::atomic::{AtomicU64, Ordering as AtomicOrdering},
+};
+
+use linked_list::{
+    dot_escape, resolve_range, CycleInfo, InvariantChecks, InvariantPolicy, LinkedList, ListError,
+    MergeHeap,
+};
+
+#[derive(Clone)]
 struct NodeIndex<T> {
     data: Option<T>,
     next: Option<usize>,
+    generation: u32, // увеличивается при каждом освобождении слота
+}
+
+// Индекс слота вместе с поколением и id списка: после освобождения слота старый дескриптор
+// перестаёт к нему подходить, даже если слот снова занят, а в другом списке (после divide_at,
+// join или clone) не подходит вовсе
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Handle {
+    list: u64,
+    index: usize,
+    generation: u32,
 }
 
 struct ListNodeIndex<T> {
     nodes: Vec<NodeIndex<T>>,
     head: Option<usize>,
     free_list: Vec<usize>, //вектор из свободных индексов
+    len: usize,
+    invariants: InvariantChecks,
+    id: u64,
+}
+
+static NEXT_LIST_ID: AtomicU64 = AtomicU64::new(0);
+
+fn next_list_id() -> u64 {
+    NEXT_LIST_ID.fetch_add(1, AtomicOrdering::Relaxed)
+}
+
+// Копия получает свой id, дескрипторы оригинала к ней не подходят
+impl<T: Clone> Clone for ListNodeIndex<T> {
+    fn clone(&self) -> Self {
+        Self {
+            nodes: self.nodes.clone(),
+            head: self.head,
+            free_list: self.free_list.clone(),
+            len: self.len,
+            invariants: self.invariants.clone(),
+            id: next_list_id(),
+        }
+    }
 }
 
 // Для collect() в create_and_drop_large_list()
@@ -28,14 +77,176 @@ struct IntoIterIndex<T> {
 impl<T> Iterator for IntoIterIndex<T> {
     type Item = T;
 
+    // Счётчик не даёт уйти на второй круг, если список закольцован
     fn next(&mut self) -> Option<Self::Item> {
+        if self.list.len == 0 {
+            return None;
+        }
         self.list.pop_head()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
 }
 
+impl<T> ExactSizeIterator for IntoIterIndex<T> {}
+
+// Идёт по ссылкам, не собирая слоты заранее. Ссылки на данные разных узлов выдаются через
+// указатель на вектор, потому что заимствование вектора не делится по произвольным индексам
+struct IterMutIndex<'a, T> {
+    nodes: *mut NodeIndex<T>,
+    slots: usize,
+    current: Option<usize>,
+    len: usize,
+    _marker: PhantomData<&'a mut [NodeIndex<T>]>,
+}
+
+// SAFETY: итератор ведёт себя как &mut [NodeIndex<T>]
+unsafe impl<T: Send> Send for IterMutIndex<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMutIndex<'_, T> {}
+
+impl<'a, T> Iterator for IterMutIndex<'a, T> {
+    type Item = &'a mut T;
+
+    // Счётчик не даёт уйти в бесконечный цикл, если список закольцован
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let ind = self.current.filter(|&ind| ind < self.slots)?;
+        self.len -= 1;
+        // SAFETY: ind в пределах вектора, который итератор занимает на 'a. Первые len узлов от
+        // головы — разные слоты: цикл замыкается только после последнего узла, поэтому каждый
+        // слот отдаётся не больше одного раза. Ссылка берётся только на поле data, next читается
+        // без ссылки на узел
+        unsafe {
+            let node = self.nodes.add(ind);
+            self.current = (*node).next;
+            (*node).data.as_mut()
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMutIndex<'_, T> {}
+
 struct IterIndex<'a, T> {
     list: &'a ListNodeIndex<T>,
     current: Option<usize>,
+    len: usize,
+}
+
+// Отвязывает подходящие узлы по мере обхода, их слоты уходят в free_list
+struct ExtractIfIndex<'a, T, F: FnMut(&mut T) -> bool> {
+    list: &'a mut ListNodeIndex<T>,
+    prev: Option<usize>,
+    current: Option<usize>,
+    remaining: usize,
+    pred: F,
+}
+
+impl<T, F: FnMut(&mut T) -> bool> Iterator for ExtractIfIndex<'_, T, F> {
+    type Item = T;
+
+    // Счётчик не даёт уйти в бесконечный цикл, если список закольцован
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(ind) = self.current.filter(|_| self.remaining > 0) {
+            self.remaining -= 1;
+            self.current = self.list.nodes[ind].next;
+            let data = self.list.nodes[ind]
+                .data
+                .as_mut()
+                .expect("Linked node holds a value");
+            if (self.pred)(data) {
+                return Some(self.list.unlink_after(self.prev, ind));
+            }
+            self.prev = Some(ind);
+        }
+        None
+    }
+}
+
+// Недочитанные элементы диапазона удаляются при drop, как у Vec::drain
+struct DrainIndex<'a, T> {
+    list: &'a mut ListNodeIndex<T>,
+    prev: Option<usize>,
+    remaining: usize,
+}
+
+impl<T> Iterator for DrainIndex<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let ind = match self.prev {
+            Some(prev) => self.list.nodes[prev].next,
+            None => self.list.head,
+        }?;
+        Some(self.list.unlink_after(self.prev, ind))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for DrainIndex<'_, T> {}
+
+impl<T> Drop for DrainIndex<'_, T> {
+    fn drop(&mut self) {
+        for _ in self.by_ref() {}
+    }
+}
+
+// Цепочки, которые сортировка или слияние ещё не вернули в список. Если compare паникует,
+// Drop дописывает их после tail: порядок тогда не определён, но узлы не теряются и длина
+// остаётся верной
+struct PendingChains<'a, T> {
+    list: &'a mut ListNodeIndex<T>,
+    tail: Option<usize>,
+    left: Option<usize>,
+    right: Option<usize>,
+    rest: Option<usize>,
+}
+
+impl<T> PendingChains<'_, T> {
+    // Сливает left и right и подвешивает результат после tail
+    fn merge<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: &mut F) {
+        loop {
+            let take_right = match (self.left, self.right) {
+                (Some(l), Some(r)) => {
+                    let nodes = &self.list.nodes;
+                    let l_data = nodes[l].data.as_ref().expect("Linked node holds a value");
+                    let r_data = nodes[r].data.as_ref().expect("Linked node holds a value");
+                    // При равенстве берётся левый узел, поэтому сортировка устойчива
+                    compare(r_data, l_data) == Ordering::Less
+                }
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (None, None) => return,
+            };
+
+            let side = if take_right { &mut self.right } else { &mut self.left };
+            let ind = side.expect("side checked above");
+            *side = self.list.nodes[ind].next;
+            self.tail = self.list.link_after(self.tail, ind);
+        }
+    }
+}
+
+impl<T> Drop for PendingChains<'_, T> {
+    fn drop(&mut self) {
+        for chain in [self.left.take(), self.right.take(), self.rest.take()] {
+            self.tail = self.list.append_chain(self.tail, chain);
+        }
+    }
 }
 
 struct SlowFastIter<'a, T> {
@@ -57,9 +268,7 @@ impl<'a, T> Iterator for SlowFastIter<'a, T> {
         self.slow = self.slow.and_then(|i| self.nodes[i].next);
         self.fast = self.fast.and_then(|i| self.nodes[i].next).and_then(|i| self.nodes[i].next);
 
-        if self.slow.is_none() {
-            return None;
-        }
+        self.slow?;
 
         Some((self.slow, self.fast))
     }
@@ -68,9 +277,14 @@ impl<'a, T> Iterator for SlowFastIter<'a, T> {
 impl<'a, T> Iterator for IterIndex<'a, T> {
     type Item = &'a T;
 
+    // Счётчик не даёт уйти в бесконечный цикл, если список закольцован
     fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
         let ind = self.current?;
         self.current = self.list.nodes[ind].next;
+        self.len -= 1;
         Some(
             self.list.nodes[ind]
                 .data
@@ -78,10 +292,17 @@ impl<'a, T> Iterator for IterIndex<'a, T> {
                 .expect("Expected successful conversion"),
         )
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
 }
 
+impl<T> ExactSizeIterator for IterIndex<'_, T> {}
+
 impl<T> Extend<T> for ListNodeIndex<T> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.check_invariants();
         let mut iter = iter.into_iter();
         let (lower, upper) = iter.size_hint();
 
@@ -89,27 +310,51 @@ impl<T> Extend<T> for ListNodeIndex<T> {
             self.reserve(lower);
         }
 
+        // Элементы добавляются в конец списка, как у коллекций std. Последний узел ищется не
+        // дальше len шагов, даже если список закольцован
+        let mut last = self.iter_nodes().take(self.len).last();
         for item in iter {
-            self.push_head(item);
+            let ind = self.push_node(item, None);
+            match last {
+                Some(last_ind) => self.nodes[last_ind].next = Some(ind),
+                None => self.head = Some(ind),
+            }
+            last = Some(ind);
         }
     }
 }
 
-impl<T> ListNodeIndex<T> {
-    fn new() -> Self {
+impl<'a, T: Copy + 'a> Extend<&'a T> for ListNodeIndex<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T> Default for ListNodeIndex<T> {
+    fn default() -> Self {
         Self {
             nodes: Vec::new(),
             head: None,
             free_list: Vec::new(),
+            len: 0,
+            invariants: InvariantChecks::default(),
+            id: next_list_id(),
         }
     }
+}
 
-    fn reserve(&mut self, additional: usize) {
-        self.nodes.reserve(additional);
+impl<T> ListNodeIndex<T> {
+    fn new() -> Self {
+        Self::default()
     }
 
-    fn is_empty(&self) -> bool {
-        self.head.is_none()
+    fn with_invariant_policy(mut self, policy: InvariantPolicy) -> Self {
+        self.invariants = InvariantChecks::new(policy);
+        self
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
     }
 
     fn get_index_at(&self, position: usize) -> Option<usize> {
@@ -126,20 +371,46 @@ impl<T> ListNodeIndex<T> {
         })         
     }
 
-    fn into_iter(self) -> IntoIterIndex<T> {
-        IntoIterIndex { list: self }
-    }
-
     fn iter(&self) -> IterIndex<'_, T> {
         IterIndex {
             list: self,
             current: self.head,
+            len: self.len,
+        }
+    }
+
+    fn iter_mut(&mut self) -> IterMutIndex<'_, T> {
+        IterMutIndex {
+            nodes: self.nodes.as_mut_ptr(),
+            slots: self.nodes.len(),
+            current: self.head,
+            len: self.len,
+            _marker: PhantomData,
         }
     }
 
+    // Проверка выполняется, только если этого требует политика списка
+    fn try_check_invariants(&self) -> Result<(), ListError> {
+        if self.invariants.due() && self.has_cycle() {
+            return Err(ListError::CycleDetected);
+        }
+        Ok(())
+    }
+
     fn check_invariants(&self) {
-        if self.has_cycle() {
-            panic!("Cycle detected in linked list!");
+        if let Err(err) = self.try_check_invariants() {
+            match self.find_cycle() {
+                Some(info) => panic!("{err}: {info:?}"),
+                None => panic!("{err}"),
+            }
+        }
+    }
+
+    // Вызывать только для списка без цикла
+    fn out_of_bounds(&self, position: usize) -> ListError {
+        ListError::OutOfBounds {
+            position,
+            len: self.len,
         }
     }
 
@@ -151,34 +422,709 @@ impl<T> ListNodeIndex<T> {
                 self.nodes.len()
             }
         }; // Получить новый индекс
-        let node = NodeIndex {
-            data: Some(data),
-            next,
-        };
-
         if ind >= self.nodes.len() {
-            self.nodes.push(node);
+            self.nodes.push(NodeIndex {
+                data: Some(data),
+                next,
+                generation: 0,
+            });
         } else {
-            self.nodes[ind] = node;
+            let node = &mut self.nodes[ind];
+            node.data = Some(data);
+            node.next = next;
         }
+        self.len += 1;
         ind
     }
 
+    fn handle(&self, ind: usize) -> Handle {
+        Handle {
+            list: self.id,
+            index: ind,
+            generation: self.nodes[ind].generation,
+        }
+    }
+
+    // Индекс слота, если дескриптор ещё указывает на живой элемент
+    fn resolve(&self, handle: Handle) -> Option<usize> {
+        if handle.list != self.id {
+            return None;
+        }
+        let node = self.nodes.get(handle.index)?;
+        (node.generation == handle.generation && node.data.is_some()).then_some(handle.index)
+    }
+
+    fn insert_head(&mut self, data: T) -> Handle {
+        let ind = self.push_node(data, self.head);
+        self.head = Some(ind);
+        self.handle(ind)
+    }
+
+    fn insert_after(&mut self, handle: Handle, data: T) -> Result<Handle, ListError> {
+        let prev = self.resolve(handle).ok_or(ListError::StaleHandle)?;
+        let ind = self.push_node(data, self.nodes[prev].next);
+        self.nodes[prev].next = Some(ind);
+        Ok(self.handle(ind))
+    }
+
+    fn get(&self, handle: Handle) -> Option<&T> {
+        let ind = self.resolve(handle)?;
+        self.nodes[ind].data.as_ref()
+    }
+
+    fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        let ind = self.resolve(handle)?;
+        self.nodes[ind].data.as_mut()
+    }
+
+    // Предшественника в односвязном списке приходится искать обходом, поэтому O(n)
+    fn remove(&mut self, handle: Handle) -> Option<T> {
+        let ind = self.resolve(handle)?;
+        let prev = if self.head == Some(ind) {
+            None
+        } else {
+            let prev = self
+                .iter_nodes()
+                .take(self.len)
+                .find(|&prev| self.nodes[prev].next == Some(ind))?;
+            Some(prev)
+        };
+        Some(self.unlink_after(prev, ind))
+    }
+
+    // Убирает узел ind, стоящий после prev (или голову), и возвращает его слот в free_list
+    fn unlink_after(&mut self, prev: Option<usize>, ind: usize) -> T {
+        let next = self.nodes[ind].next.take();
+        match prev {
+            Some(prev) => self.nodes[prev].next = next,
+            None => self.head = next,
+        }
+        self.free_list.push(ind);
+        self.len -= 1;
+        self.nodes[ind].generation = self.nodes[ind].generation.wrapping_add(1);
+        self.nodes[ind]
+            .data
+            .take()
+            .expect("Linked node holds a value")
+    }
+
+    fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_mut(|data| f(data));
+    }
+
+    fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        self.extract_if(|data| !f(data)).for_each(drop);
+    }
+
+    fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, pred: F) -> ExtractIfIndex<'_, T, F> {
+        self.check_invariants();
+        ExtractIfIndex {
+            current: self.head,
+            remaining: self.len,
+            list: self,
+            prev: None,
+            pred,
+        }
+    }
+
+    fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> DrainIndex<'_, T> {
+        self.check_invariants();
+        let range = resolve_range(range, self.len);
+        let prev = match range.start {
+            0 => None,
+            start => self.get_index_at(start - 1),
+        };
+        DrainIndex {
+            list: self,
+            prev,
+            remaining: range.len(),
+        }
+    }
+
+    // Последний узел начинает указывать на узел с индексом position, позиция считается с нуля
+    fn try_make_cycle_at(&mut self, position: usize) -> Result<(), ListError> {
+        self.try_check_invariants()?;
+        if self.is_empty() {
+            return Err(ListError::Empty);
+        }
+
+        let target_ind = self
+            .get_index_at(position)
+            .ok_or_else(|| self.out_of_bounds(position))?;
+        let last_ind = self
+            .iter_nodes()
+            .take(self.len)
+            .last()
+            .expect("list is not empty");
+        self.nodes[last_ind].next = Some(target_ind);
+        Ok(())
+    }
+
+    // Как в исходной версии, позиция считается с единицы: make_cycle_at(1) замыкает последний
+    // узел на голову. Пустой список не меняется
+    fn make_cycle_at(&mut self, position: usize) {
+        if self.is_empty() {
+            return;
+        }
+        let index = position.checked_sub(1).expect("make_cycle_at positions start at 1");
+        if let Err(err) = self.try_make_cycle_at(index) {
+            panic!("make_cycle_at failed: {err}");
+        }
+    }
+
+    fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    // Восходящая сортировка слиянием: перевязываются только индексы next, рекурсии нет.
+    // Обратная ссылка закольцованного списка отрезается, сортируются ровно len узлов
+    fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        self.check_invariants();
+
+        let len = self.len;
+        let head = self.head;
+        self.cut_after(head, len);
+        let mut width = 1;
+        while width < len {
+            let rest = self.head.take();
+            let mut pending = PendingChains {
+                list: self,
+                tail: None,
+                left: None,
+                right: None,
+                rest,
+            };
+            while pending.rest.is_some() {
+                pending.left = pending.rest.take();
+                pending.right = pending.list.cut_after(pending.left, width);
+                pending.rest = pending.list.cut_after(pending.right, width);
+                pending.merge(&mut compare);
+            }
+            width *= 2;
+        }
+    }
+
+    // Отрезает первые n узлов цепочки и возвращает оставшуюся часть
+    fn cut_after(&mut self, start: Option<usize>, n: usize) -> Option<usize> {
+        let mut current = start?;
+        for _ in 1..n {
+            current = self.nodes[current].next?;
+        }
+        self.nodes[current].next.take()
+    }
+
+    // Переносит элементы other в порядке списка в свои свободные слоты, а когда их нет — в новые,
+    // и возвращает голову их цепочки; сама цепочка никуда не подвешивается. Свободные слоты other
+    // не переносятся, поэтому вектор не растёт от повторных divide_at и join
+    fn absorb(&mut self, other: Self) -> Option<usize> {
+        let (mut head, mut tail): (_, Option<usize>) = (None, None);
+        for data in other {
+            let ind = self.push_node(data, None);
+            match tail {
+                Some(tail_ind) => self.nodes[tail_ind].next = Some(ind),
+                None => head = Some(ind),
+            }
+            tail = Some(ind);
+        }
+        head
+    }
+
+    fn merge_sorted(a: Self, b: Self) -> Self
+    where
+        T: Ord,
+    {
+        Self::merge_sorted_by(a, b, T::cmp)
+    }
+
+    fn merge_sorted_by_key<K: Ord, F: FnMut(&T) -> K>(a: Self, b: Self, mut f: F) -> Self {
+        Self::merge_sorted_by(a, b, |x, y| f(x).cmp(&f(y)))
+    }
+
+    // Узлы b переезжают в вектор a, после чего цепочки перевязываются; при равенстве первым
+    // идёт элемент из a
+    fn merge_sorted_by<F: FnMut(&T, &T) -> Ordering>(a: Self, b: Self, mut compare: F) -> Self {
+        a.check_invariants();
+        b.check_invariants();
+
+        let mut merged = a;
+        let right = merged.absorb(b);
+        let left = merged.head.take();
+        PendingChains {
+            list: &mut merged,
+            tail: None,
+            left,
+            right,
+            rest: None,
+        }
+        .merge(&mut compare);
+        merged
+    }
+
+    fn merge_k_sorted(lists: Vec<Self>) -> Self
+    where
+        T: Ord,
+    {
+        Self::merge_k_sorted_by(lists, T::cmp)
+    }
+
+    fn merge_k_sorted_by_key<K: Ord, F: FnMut(&T) -> K>(lists: Vec<Self>, mut f: F) -> Self {
+        Self::merge_k_sorted_by(lists, |x, y| f(x).cmp(&f(y)))
+    }
+
+    // O(N log k): в куче лежит по одной голове от каждого списка. При равенстве первым идёт
+    // элемент из списка, который стоит в lists раньше
+    fn merge_k_sorted_by<F: FnMut(&T, &T) -> Ordering>(lists: Vec<Self>, mut compare: F) -> Self {
+        let mut merged = Self::new();
+        let mut heap = MergeHeap::with_capacity(lists.len());
+        for (source, list) in lists.into_iter().enumerate() {
+            list.check_invariants();
+            if let Some(head) = merged.absorb(list) {
+                heap.push(head, source, &mut merged.node_order(&mut compare));
+            }
+        }
+
+        let mut tail = None;
+        loop {
+            let Some((ind, source)) = heap.pop(&mut merged.node_order(&mut compare)) else {
+                return merged;
+            };
+            if let Some(next) = merged.nodes[ind].next {
+                heap.push(next, source, &mut merged.node_order(&mut compare));
+            }
+            tail = merged.link_after(tail, ind);
+        }
+    }
+
+    // Сравнение узлов по индексам для MergeHeap
+    fn node_order<'a, F: FnMut(&T, &T) -> Ordering>(
+        &'a self,
+        compare: &'a mut F,
+    ) -> impl FnMut(&usize, &usize) -> Ordering + 'a {
+        move |&a, &b| {
+            let a_data = self.nodes[a].data.as_ref().expect("Linked node holds a value");
+            let b_data = self.nodes[b].data.as_ref().expect("Linked node holds a value");
+            compare(a_data, b_data)
+        }
+    }
+
+    // Подвешивает узел ind после tail (или делает головой); возвращает новый хвост
+    fn link_after(&mut self, tail: Option<usize>, ind: usize) -> Option<usize> {
+        self.nodes[ind].next = None;
+        match tail {
+            Some(tail_ind) => self.nodes[tail_ind].next = Some(ind),
+            None => self.head = Some(ind),
+        }
+        Some(ind)
+    }
+
+    fn append_chain(&mut self, mut tail: Option<usize>, mut chain: Option<usize>) -> Option<usize> {
+        while let Some(ind) = chain {
+            chain = self.nodes[ind].next;
+            tail = self.link_after(tail, ind);
+        }
+        tail
+    }
+
+    fn reverse_chain(&mut self, mut chain: Option<usize>) -> Option<usize> {
+        let mut reversed = None;
+        while let Some(ind) = chain {
+            chain = std::mem::replace(&mut self.nodes[ind].next, reversed);
+            reversed = Some(ind);
+        }
+        reversed
+    }
+
+    // Медленный итератор идёт на шаг, быстрый на два; возвращает узел перед серединой и середину.
+    // При чётной длине серединой считается первый узел второй половины
+    fn middle_indices(&self) -> (Option<usize>, Option<usize>) {
+        self.check_invariants();
+
+        let mut slow_iter = self.iter_nodes();
+        let mut fast_iter = self.iter_nodes();
+
+        let mut prev = None;
+        let mut middle = slow_iter.next();
+        while fast_iter.next().is_some() && fast_iter.next().is_some() {
+            prev = middle;
+            middle = slow_iter.next();
+        }
+        (prev, middle)
+    }
+
+    fn middle(&self) -> Option<&T> {
+        let (_, middle) = self.middle_indices();
+        middle.and_then(|ind| self.nodes[ind].data.as_ref())
+    }
+
+    // nth_from_end(0) — последний элемент; ведущий итератор уходит на k + 1 элементов вперёд
+    fn nth_from_end(&self, k: usize) -> Option<&T> {
+        self.check_invariants();
+
+        let mut lead = self.iter();
+        lead.nth(k)?;
+        let mut trail = self.iter();
+        let mut current = trail.next();
+        for _ in lead {
+            current = trail.next();
+        }
+        current
+    }
+
+    // Вторая половина временно разворачивается и сравнивается с первой, затем восстанавливается
+    fn is_palindrome(&mut self) -> bool
+    where
+        T: PartialEq,
+    {
+        let (_, middle) = self.middle_indices();
+        let reversed = self.reverse_chain(middle);
+
+        let (mut front, mut back) = (self.head, reversed);
+        let mut result = true;
+        for _ in 0..self.len / 2 {
+            let (Some(f), Some(b)) = (front, back) else {
+                break;
+            };
+            if self.nodes[f].data != self.nodes[b].data {
+                result = false;
+                break;
+            }
+            front = self.nodes[f].next;
+            back = self.nodes[b].next;
+        }
+
+        // Узел перед серединой по-прежнему указывает на середину, она снова станет началом цепочки
+        self.reverse_chain(reversed);
+        result
+    }
+
+    // Вторая половина начинается с middle(), при нечётной длине она длиннее на один элемент
+    fn split_halves(mut self) -> (Self, Self) {
+        self.check_invariants();
+        let second = self.split_off(self.len / 2);
+        (self, second)
+    }
+
+    // Отрезает узлы начиная с position в новый список с компактным вектором. Слоты отрезанных
+    // узлов освобождаются, а у нового списка свой id, поэтому их старые дескрипторы не подходят
+    // ни к одному из списков. Вызывать для position <= len
+    fn split_off(&mut self, position: usize) -> Self {
+        let mut second = Self {
+            invariants: self.invariants.clone(),
+            ..Self::default()
+        };
+        second.reserve(self.len - position);
+
+        let prev = position
+            .checked_sub(1)
+            .map(|prev| self.get_index_at(prev).expect("position is in bounds"));
+        let mut tail = None;
+        for _ in position..self.len {
+            let link = match prev {
+                Some(prev) => self.nodes[prev].next,
+                None => self.head,
+            };
+            let Some(ind) = link else {
+                break;
+            };
+            let data = self.unlink_after(prev, ind);
+            let moved = second.push_node(data, None);
+            tail = second.link_after(tail, moved);
+        }
+        // Если список был закольцован, последний узел указывал назад; эта ссылка уже не нужна
+        match prev {
+            Some(prev) => self.nodes[prev].next = None,
+            None => self.head = None,
+        }
+        second
+    }
+
+    fn reverse(&mut self) {
+        self.check_invariants();
+        self.head = self.reverse_chain(self.head);
+    }
+
+    fn rotate_left(&mut self, k: usize) {
+        self.check_invariants();
+        if self.len == 0 || k.is_multiple_of(self.len) {
+            return;
+        }
+        let first = self.head.take();
+        let second = self.cut_after(first, k % self.len);
+        let tail = self.append_chain(None, second);
+        self.append_chain(tail, first);
+    }
+
+    fn rotate_right(&mut self, k: usize) {
+        if self.len == 0 {
+            return;
+        }
+        self.rotate_left(self.len - k % self.len);
+    }
+
+    // Разворачивает каждую полную группу из k узлов, неполный остаток не трогается
+    fn reverse_k_groups(&mut self, k: usize) {
+        assert_ne!(k, 0, "group size must be positive");
+        self.check_invariants();
+        let mut rest = self.head.take();
+        let mut tail = None;
+        for _ in 0..self.len / k {
+            let group = rest;
+            rest = self.cut_after(group, k);
+            let reversed = self.reverse_chain(group);
+            tail = self.append_chain(tail, reversed);
+        }
+        self.append_chain(tail, rest);
+    }
+
+    // L0, Ln, L1, Ln-1, ...: вторая половина разворачивается и вплетается в первую
+    fn reorder(&mut self) {
+        self.check_invariants();
+        let mut first = self.head.take();
+        let second = self.cut_after(first, self.len.div_ceil(2));
+        let mut second = self.reverse_chain(second);
+        let mut tail = None;
+        while let Some(ind) = first {
+            first = self.nodes[ind].next;
+            tail = self.link_after(tail, ind);
+            if let Some(ind) = second {
+                second = self.nodes[ind].next;
+                tail = self.link_after(tail, ind);
+            }
+        }
+    }
+
+    // Сначала элементы с чётными позициями, затем с нечётными, порядок внутри групп сохраняется
+    fn odd_even(&mut self) {
+        self.check_invariants();
+        let mut rest = self.head.take();
+        let (mut even_tail, mut odd_head, mut odd_tail): (_, _, Option<usize>) = (None, None, None);
+        let mut to_odd = false;
+        while let Some(ind) = rest {
+            rest = self.nodes[ind].next.take();
+            if to_odd {
+                match odd_tail {
+                    Some(tail_ind) => self.nodes[tail_ind].next = Some(ind),
+                    None => odd_head = Some(ind),
+                }
+                odd_tail = Some(ind);
+            } else {
+                even_tail = self.link_after(even_tail, ind);
+            }
+            to_odd = !to_odd;
+        }
+        if let Some(tail_ind) = even_tail {
+            self.nodes[tail_ind].next = odd_head;
+        }
+    }
+
+    fn has_cycle(&self) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        SlowFastIter::new(&self.nodes, self.head).any(|(slow, fast)| slow == fast)
+    }
+
+    // Алгоритм Флойда: после встречи указателей внутри цикла один возвращается в голову, и оба
+    // идут по шагу до входа в цикл
+    fn find_cycle(&self) -> Option<CycleInfo> {
+        let (meeting, _) = SlowFastIter::new(&self.nodes, self.head).find(|(slow, fast)| slow == fast)?;
+        let mut slow = meeting?;
+
+        let mut entry = self.head.expect("list with cycle is not empty");
+        let mut entry_position = 0;
+        while entry != slow {
+            entry = self.nodes[entry].next.expect("cycle has no end");
+            slow = self.nodes[slow].next.expect("cycle has no end");
+            entry_position += 1;
+        }
+
+        let mut cycle_len = 1;
+        let mut ind = self.nodes[entry].next.expect("cycle has no end");
+        while ind != entry {
+            ind = self.nodes[ind].next.expect("cycle has no end");
+            cycle_len += 1;
+        }
+
+        Some(CycleInfo {
+            entry_position,
+            cycle_len,
+            tail_position: entry_position + cycle_len - 1,
+        })
+    }
+
+    // Обрезает обратную ссылку, которую создал make_cycle_at; возвращает, где был цикл
+    fn break_cycle(&mut self) -> Option<CycleInfo> {
+        let info = self.find_cycle()?;
+        let tail_ind = self
+            .get_index_at(info.tail_position)
+            .expect("tail is inside the list");
+        self.nodes[tail_ind].next = None;
+        Some(info)
+    }
+
+    // Индексы узлов от головы; при цикле каждый узел один раз. Длине списка не доверяет, чтобы
+    // дамп показывал и испорченный список
+    fn dump_order(&self, cycle: Option<CycleInfo>) -> Vec<usize> {
+        match cycle {
+            Some(info) => self.iter_nodes().take(info.tail_position + 1).collect(),
+            None => self.iter_nodes().collect(),
+        }
+    }
+
+    // Граф для Graphviz: узлы подписаны индексом в арене, свободные слоты серые и пунктирные,
+    // обратная ссылка цикла красная. Занятые слоты, до которых не дойти от головы, обведены
+    // красным: так выглядит потерянный узел или испорченный free_list
+    fn write_dot(&self, out: &mut impl fmt::Write) -> fmt::Result
+    where
+        T: fmt::Debug,
+    {
+        let cycle = self.find_cycle();
+        let order = self.dump_order(cycle);
+        let mut reachable = vec![false; self.nodes.len()];
+        for &ind in &order {
+            reachable[ind] = true;
+        }
+
+        writeln!(out, "digraph ListNodeIndex {{")?;
+        writeln!(out, "    rankdir=LR;")?;
+        writeln!(out, "    node [shape=box];")?;
+        writeln!(out, "    head [shape=plaintext];")?;
+        for (ind, node) in self.nodes.iter().enumerate() {
+            match &node.data {
+                Some(data) => {
+                    let label = dot_escape(&format!("#{ind}\n{data:?}"));
+                    let style = if reachable[ind] { "" } else { ", color=red" };
+                    writeln!(out, "    n{ind} [label=\"{label}\"{style}];")?;
+                }
+                None => writeln!(
+                    out,
+                    "    n{ind} [label=\"#{ind}\\nfree\", style=dashed, color=gray];"
+                )?,
+            }
+        }
+
+        if let Some(&first) = order.first() {
+            writeln!(out, "    head -> n{first};")?;
+        }
+        for pair in order.windows(2) {
+            writeln!(out, "    n{} -> n{};", pair[0], pair[1])?;
+        }
+        if let Some(info) = cycle {
+            writeln!(
+                out,
+                "    n{} -> n{} [style=dashed, color=red, label=\"cycle\"];",
+                order[info.tail_position], order[info.entry_position]
+            )?;
+        }
+
+        // free_list — стек: следующим займётся последний индекс
+        if !self.free_list.is_empty() {
+            writeln!(out, "    free [shape=plaintext];")?;
+            let mut prev = String::from("free");
+            for &ind in &self.free_list {
+                writeln!(out, "    {prev} -> n{ind} [style=dotted, color=gray];")?;
+                prev = format!("n{ind}");
+            }
+        }
+        writeln!(out, "}}")
+    }
+
+    fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut out = String::new();
+        self.write_dot(&mut out).expect("writing to a String does not fail");
+        out
+    }
+
+    // Сначала узлы в порядке списка, затем остальные слоты арены и содержимое free_list
+    fn write_ascii_diagram(&self, out: &mut impl fmt::Write) -> fmt::Result
+    where
+        T: fmt::Debug,
+    {
+        let cycle = self.find_cycle();
+        let order = self.dump_order(cycle);
+        let mut reachable = vec![false; self.nodes.len()];
+        for &ind in &order {
+            reachable[ind] = true;
+        }
+
+        match order.first() {
+            Some(first) => writeln!(out, "head -> #{first}")?,
+            None => writeln!(out, "head -> None")?,
+        }
+        for (position, &ind) in order.iter().enumerate() {
+            match &self.nodes[ind].data {
+                Some(data) => write!(out, "#{ind} {data:?}")?,
+                None => write!(out, "#{ind} free")?,
+            }
+            match cycle {
+                _ if position + 1 < order.len() => writeln!(out, " -> #{}", order[position + 1])?,
+                Some(info) => writeln!(out, " -> #{} (cycle)", order[info.entry_position])?,
+                None => writeln!(out, " -> None")?,
+            }
+        }
+
+        for (ind, node) in self.nodes.iter().enumerate() {
+            match &node.data {
+                _ if reachable[ind] => {}
+                Some(data) => writeln!(out, "#{ind} {data:?} (unreachable)")?,
+                None => writeln!(out, "#{ind} free")?,
+            }
+        }
+        if !self.free_list.is_empty() {
+            let free: Vec<_> = self.free_list.iter().map(|ind| format!("#{ind}")).collect();
+            writeln!(out, "free list: {}", free.join(" "))?;
+        }
+        Ok(())
+    }
+
+    fn to_ascii_diagram(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut out = String::new();
+        self.write_ascii_diagram(&mut out)
+            .expect("writing to a String does not fail");
+        out
+    }
+}
+
+impl<T> LinkedList<T> for ListNodeIndex<T> {
+    fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn peek_head(&self) -> Option<&T> {
+        self.head.and_then(|ind| self.nodes[ind].data.as_ref())
+    }
+
     fn push_head(&mut self, data: T) {
-        let new_head_ind = self.push_node(data, self.head);
-        self.head = Some(new_head_ind);
+        self.insert_head(data);
     }
 
     fn pop_head(&mut self) -> Option<T> {
         let head_ind = self.head?;
-        let node_to_pop = &mut self.nodes[head_ind];
-        self.head = node_to_pop.next;
-
-        self.free_list.push(head_ind);
-        node_to_pop.data.take()
+        Some(self.unlink_after(None, head_ind))
     }
 
     fn join(mut self, mut other: Self) -> Self {
+        self.check_invariants();
+        other.check_invariants();
+
         if self.is_empty() {
             return other;
         }
@@ -187,91 +1133,125 @@ impl<T> ListNodeIndex<T> {
         }
         let last_ind = self
             .iter_nodes()
+            .take(self.len)
             .last()
             .expect("Expected to have last node");
-        let offset = self.nodes.len();
-        let other_len = other.nodes.len();
-
-        self.nodes.extend(other.nodes);
+        self.nodes[last_ind].next = self.absorb(other);
+        self
+    }
 
-        for i in 0..other_len {
-            let global_idx = offset + i;
-            if let Some(next) = self.nodes[global_idx].next {
-                self.nodes[global_idx].next = Some(next + offset);
-            }
+    // Первая часть остаётся в своём векторе, её дескрипторы продолжают работать
+    fn try_divide_at(mut self, position: usize) -> Result<(Self, Self), ListError> {
+        self.try_check_invariants()?;
+        if position > self.len {
+            return Err(self.out_of_bounds(position));
         }
 
-        self.nodes[last_ind].next = other.head.map(|h| h + offset);
-
-        for idx in other.free_list {
-            self.free_list.push(idx + offset);
-        }
-        self
+        let second = self.split_off(position);
+        Ok((self, second))
     }
 
-    fn append_at(&mut self, position: usize, data: T) {
+    fn try_append_at(&mut self, position: usize, data: T) -> Result<(), ListError> {
+        self.try_check_invariants()?;
+
         if position == 0 {
             self.push_head(data);
-            return;
+            return Ok(());
+        }
+        if position > self.len {
+            return Err(self.out_of_bounds(position));
         }
 
         let prev_ind = self
             .get_index_at(position - 1)
-            .expect("Position out of bounds");
+            .ok_or_else(|| self.out_of_bounds(position))?;
         let next_of_prev = self.nodes[prev_ind].next;
         let new_ind = self.push_node(data, next_of_prev);
         self.nodes[prev_ind].next = Some(new_ind);
+        Ok(())
     }
 
-    fn remove_at(&mut self, position: usize) {
+    fn try_remove_at(&mut self, position: usize) -> Result<T, ListError> {
+        self.try_check_invariants()?;
+
+        if self.is_empty() {
+            return Err(ListError::Empty);
+        }
+        if position >= self.len {
+            return Err(self.out_of_bounds(position));
+        }
+
         if position == 0 {
-            self.pop_head();
-            return;
+            return self.pop_head().ok_or(ListError::Empty);
         }
 
-        let prev_ind = self
+        let (prev_ind, ind_to_remove) = self
             .get_index_at(position - 1)
-            .expect("Position out of bounds");
-        let ind_to_remove = self.nodes[prev_ind].next.expect("Expected node to remove");
-        self.nodes[prev_ind].next = self.nodes[ind_to_remove].next;
-        self.free_list.push(ind_to_remove);
+            .and_then(|prev_ind| Some((prev_ind, self.nodes[prev_ind].next?)))
+            .ok_or_else(|| self.out_of_bounds(position))?;
+        Ok(self.unlink_after(Some(prev_ind), ind_to_remove))
     }
+}
 
-    fn make_cycle_at(&mut self, position: usize) {
-        if self.is_empty() {
-            return;
-        }
+impl<T> IntoIterator for ListNodeIndex<T> {
+    type Item = T;
+    type IntoIter = IntoIterIndex<T>;
 
-        let target_ind = self.get_index_at(position - 1).expect("Invalid position");
-        let last_ind = self.iter_nodes().last().expect("Error: empty list");
-        self.nodes[last_ind].next = Some(target_ind);
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterIndex { list: self }
     }
+}
 
-    fn has_cycle(&self) -> bool {
-        if self.is_empty() {
-            return false;
-        }
+impl<'a, T> IntoIterator for &'a ListNodeIndex<T> {
+    type Item = &'a T;
+    type IntoIter = IterIndex<'a, T>;
 
-        SlowFastIter::new(&self.nodes, self.head).any(|(slow, fast)| slow == fast)
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
+}
 
-    fn divide_at(self, position: usize) -> (Self, Self) {
-        let mut iter = self.into_iter();
+impl<'a, T> IntoIterator for &'a mut ListNodeIndex<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMutIndex<'a, T>;
 
-        let left_items: Vec<T> = iter.by_ref().take(position).collect();
-        let right_items: Vec<T> = iter.collect();
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
 
-        let mut left = ListNodeIndex::new();
-        for item in left_items.into_iter().rev() {
-            left.push_head(item);
-        }
+impl<T: PartialEq> PartialEq for ListNodeIndex<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for ListNodeIndex<T> {}
+
+impl<T: PartialOrd> PartialOrd for ListNodeIndex<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord> Ord for ListNodeIndex<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
 
-        let mut right = ListNodeIndex::new();
-        for item in right_items.into_iter().rev() {
-            right.push_head(item);
+impl<T: Hash> Hash for ListNodeIndex<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for data in self.iter() {
+            data.hash(state);
         }
+        state.write_usize(self.len);
+    }
+}
 
-        (left, right)
+impl<T: fmt::Debug> fmt::Debug for ListNodeIndex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
     }
 }
 
@@ -331,7 +1311,7 @@ mod tests {
         list.push_head(4);
         list.push_head(5);
 
-        let (a, b) = list.divide_at(3);
+        let (a, b) = list.divide_at(3).expect("divide_at failed");
 
         let left: Vec<_> = a.iter().copied().collect();
         let right: Vec<_> = b.iter().copied().collect();
@@ -376,4 +1356,611 @@ mod tests {
         list.make_cycle_at(1);
         assert!(list.has_cycle());
     }
+
+    #[test]
+    fn test_try_make_cycle_at() {
+        let mut list =
+            ListNodeIndex::<i32>::new().with_invariant_policy(InvariantPolicy::Always);
+        assert_eq!(list.try_make_cycle_at(0), Err(ListError::Empty));
+
+        list.push_head(1);
+        list.push_head(2);
+        assert_eq!(
+            list.try_make_cycle_at(2),
+            Err(ListError::OutOfBounds { position: 2, len: 2 })
+        );
+        assert_eq!(list.try_make_cycle_at(0), Ok(()));
+        assert!(list.has_cycle());
+        assert_eq!(list.try_make_cycle_at(0), Err(ListError::CycleDetected));
+    }
+
+    #[test]
+    fn test_find_and_break_cycle() {
+        for len in 1..8 {
+            for position in 0..len {
+                let mut list: ListNodeIndex<usize> = (0..len).collect();
+                assert_eq!(list.find_cycle(), None);
+
+                list.make_cycle_at(position + 1);
+                let info = CycleInfo {
+                    entry_position: position,
+                    cycle_len: len - position,
+                    tail_position: len - 1,
+                };
+                assert_eq!(list.find_cycle(), Some(info));
+
+                assert_eq!(list.break_cycle(), Some(info));
+                assert_eq!(list.break_cycle(), None);
+                assert_eq!(list.iter().copied().collect::<Vec<_>>(), (0..len).collect::<Vec<_>>());
+            }
+        }
+
+        let mut empty: ListNodeIndex<i32> = ListNodeIndex::new();
+        assert_eq!(empty.find_cycle(), None);
+        assert_eq!(empty.break_cycle(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "entry_position: 1, cycle_len: 3, tail_position: 3")]
+    fn test_check_invariants_reports_cycle() {
+        let mut list = (0..4)
+            .collect::<ListNodeIndex<i32>>()
+            .with_invariant_policy(InvariantPolicy::Always);
+        list.make_cycle_at(2);
+        list.sort();
+    }
+
+    #[test]
+    fn test_invariant_policy() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let with_policy =
+            |policy| (0..4).collect::<ListNodeIndex<i32>>().with_invariant_policy(policy);
+
+        let mut list = with_policy(InvariantPolicy::Never);
+        list.make_cycle_at(2);
+        assert_eq!(list.try_check_invariants(), Ok(()));
+        assert!(list.has_cycle());
+        // Без проверок операции всё равно не выходят за len узлов
+        assert_eq!(
+            list.try_remove_at(10),
+            Err(ListError::OutOfBounds { position: 10, len: 4 })
+        );
+        let (left, right) = list.divide_at(2).expect("position is in bounds");
+        assert_eq!(left.iter().copied().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(right.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+
+        // Первая проверка в make_cycle_at, вторая пропускается, третья находит цикл
+        let mut list = with_policy(InvariantPolicy::EveryN(2));
+        list.make_cycle_at(2);
+        assert_eq!(list.try_check_invariants(), Ok(()));
+        assert_eq!(list.try_check_invariants(), Err(ListError::CycleDetected));
+
+        let mut list = with_policy(InvariantPolicy::Always);
+        list.make_cycle_at(2);
+        assert_eq!(list.try_make_cycle_at(0), Err(ListError::CycleDetected));
+        let extended = catch_unwind(AssertUnwindSafe(|| list.extend([4])));
+        assert!(extended.is_err());
+
+        // Без проверок extend находит последний узел за len шагов, обратная ссылка пропадает
+        let mut list = with_policy(InvariantPolicy::Never);
+        list.make_cycle_at(2);
+        list.extend([4]);
+        assert!(list.find_cycle().is_none());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+
+        // Копии и части списка сохраняют политику
+        let list = with_policy(InvariantPolicy::Never);
+        assert_eq!(list.clone().invariants.policy(), InvariantPolicy::Never);
+        let (left, right) = list.clone().divide_at(1).expect("position is in bounds");
+        assert_eq!(left.invariants.policy(), InvariantPolicy::Never);
+        assert_eq!(right.invariants.policy(), InvariantPolicy::Never);
+        let (_, second) = list.split_halves();
+        assert_eq!(second.invariants.policy(), InvariantPolicy::Never);
+    }
+
+    #[test]
+    fn test_structure_dumps() {
+        let mut list: ListNodeIndex<&str> = ["a", "b", "c"].into_iter().collect();
+        assert_eq!(list.remove_at(1), Some("b"));
+        assert_eq!(
+            list.to_ascii_diagram(),
+            "head -> #0\n#0 \"a\" -> #2\n#2 \"c\" -> None\n#1 free\nfree list: #1\n"
+        );
+
+        let dot = list.to_dot();
+        assert!(dot.starts_with("digraph ListNodeIndex {\n"));
+        assert!(dot.contains("    n0 [label=\"#0\\n\\\"a\\\"\"];\n"));
+        assert!(dot.contains("    n1 [label=\"#1\\nfree\", style=dashed, color=gray];\n"));
+        assert!(dot.contains("    head -> n0;\n    n0 -> n2;\n"));
+        assert!(dot.contains("    free -> n1 [style=dotted, color=gray];\n"));
+
+        list.push_head("z");
+        list.make_cycle_at(2);
+        assert_eq!(
+            list.to_ascii_diagram(),
+            "head -> #1\n#1 \"z\" -> #0\n#0 \"a\" -> #2\n#2 \"c\" -> #0 (cycle)\n"
+        );
+        assert!(list
+            .to_dot()
+            .contains("    n2 -> n0 [style=dashed, color=red, label=\"cycle\"];\n"));
+        list.break_cycle();
+
+        // Потерянный узел и индекс занятого слота в free_list
+        list.nodes[0].next = None;
+        list.free_list.push(2);
+        assert_eq!(
+            list.to_ascii_diagram(),
+            "head -> #1\n#1 \"z\" -> #0\n#0 \"a\" -> None\n#2 \"c\" (unreachable)\nfree list: #2\n"
+        );
+        assert!(list.to_dot().contains("    n2 [label=\"#2\\n\\\"c\\\"\", color=red];\n"));
+
+        let empty: ListNodeIndex<i32> = ListNodeIndex::new();
+        assert_eq!(empty.to_ascii_diagram(), "head -> None\n");
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut list: ListNodeIndex<i32> = [5, 3, 9, 1, 3, 7, 0, 2].into_iter().collect();
+        list.pop_head();
+        list.push_head(4);
+        list.sort();
+        let values: Vec<_> = list.iter().copied().collect();
+        assert_eq!(values, vec![0, 1, 2, 3, 3, 4, 7, 9]);
+
+        let mut empty: ListNodeIndex<i32> = ListNodeIndex::new();
+        empty.sort();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_sort_is_stable() {
+        let mut list = ListNodeIndex::new();
+        let mut model = Vec::new();
+        for i in 0..100 {
+            let item = ((i * 37) % 10, i);
+            list.push_head(item);
+            model.insert(0, item);
+        }
+
+        list.sort_by_key(|&(key, _)| key);
+        model.sort_by_key(|&(key, _)| key);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), model);
+
+        list.sort_by_key(|&(_, i)| std::cmp::Reverse(i));
+        model.sort_by_key(|&(_, i)| std::cmp::Reverse(i));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), model);
+    }
+
+    #[test]
+    fn test_panicking_compare_keeps_nodes() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let panicking = |limit: usize| {
+            let mut calls = 0;
+            move |a: &i32, b: &i32| {
+                calls += 1;
+                assert!(calls < limit, "compare gave up");
+                a.cmp(b)
+            }
+        };
+
+        // После паники сортировки все элементы остаются в списке, длина верная
+        let mut list: ListNodeIndex<i32> = [5, 1, 4, 2, 3, 0, 6, 7].into_iter().collect();
+        let result = catch_unwind(AssertUnwindSafe(|| list.sort_by(panicking(6))));
+        assert!(result.is_err());
+        assert_eq!(list.len(), 8);
+        assert_eq!(list.iter().count(), 8);
+        let mut values: Vec<_> = list.iter().copied().collect();
+        values.sort_unstable();
+        assert_eq!(values, (0..8).collect::<Vec<_>>());
+        list.sort();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+
+        // Без проверок закольцованный список сортируется как len узлов
+        let mut list: ListNodeIndex<i32> =
+            ListNodeIndex::from_iter([3, 1, 2, 0]).with_invariant_policy(InvariantPolicy::Never);
+        assert_eq!(list.try_make_cycle_at(1), Ok(()));
+        list.sort();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert!(list.find_cycle().is_none());
+    }
+
+    #[test]
+    fn test_sort_large_list() {
+        let mut list: ListNodeIndex<u64> = (0..1_000_000u64)
+            .map(|i| i.wrapping_mul(2_654_435_761) % 1_000_003)
+            .collect();
+        list.sort();
+        assert!(list.iter().zip(list.iter().skip(1)).all(|(a, b)| a <= b));
+    }
+
+    #[test]
+    fn test_for_loops() {
+        let mut list: ListNodeIndex<i32> = (1..=3).collect();
+        list.pop_head();
+        list.push_head(1);
+        for data in &mut list {
+            *data *= 10;
+        }
+
+        let mut seen = Vec::new();
+        for data in &list {
+            seen.push(*data);
+        }
+        assert_eq!(seen, vec![10, 20, 30]);
+
+        let owned: Vec<_> = list.into_iter().collect();
+        assert_eq!(owned, vec![10, 20, 30]);
+    }
+
+    // Сравнивает операцию над списком с той же операцией над Vec для длин 0..12
+    fn check_against_model(op: impl Fn(&mut ListNodeIndex<i32>), model: impl Fn(&mut Vec<i32>)) {
+        for len in 0..12 {
+            // Начинаем с перемешанных слотов, чтобы порядок узлов не совпадал с порядком в векторе
+            let mut list: ListNodeIndex<i32> = (0..len).rev().collect();
+            list.reverse();
+            let mut expected: Vec<i32> = (0..len).collect();
+            op(&mut list);
+            model(&mut expected);
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected, "len = {len}");
+            assert_eq!(list.len(), expected.len());
+            list.check_invariants();
+        }
+    }
+
+    #[test]
+    fn test_reverse() {
+        check_against_model(|list| list.reverse(), |v| v.reverse());
+    }
+
+    #[test]
+    fn test_rotate() {
+        for k in 0..15 {
+            check_against_model(
+                |list| list.rotate_left(k),
+                |v| {
+                    if !v.is_empty() {
+                        let k = k % v.len();
+                        v.rotate_left(k);
+                    }
+                },
+            );
+            check_against_model(
+                |list| list.rotate_right(k),
+                |v| {
+                    if !v.is_empty() {
+                        let k = k % v.len();
+                        v.rotate_right(k);
+                    }
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_reverse_k_groups() {
+        for k in 1..6 {
+            check_against_model(
+                |list| list.reverse_k_groups(k),
+                |v| v.chunks_exact_mut(k).for_each(|group| group.reverse()),
+            );
+        }
+    }
+
+    #[test]
+    fn test_reorder() {
+        check_against_model(
+            |list| list.reorder(),
+            |v| {
+                let mut reordered = Vec::new();
+                let (mut i, mut j) = (0, v.len());
+                while i < j {
+                    reordered.push(v[i]);
+                    i += 1;
+                    if i < j {
+                        j -= 1;
+                        reordered.push(v[j]);
+                    }
+                }
+                *v = reordered;
+            },
+        );
+    }
+
+    #[test]
+    fn test_odd_even() {
+        check_against_model(
+            |list| list.odd_even(),
+            |v| {
+                let odd_even = v.iter().step_by(2).chain(v.iter().skip(1).step_by(2));
+                *v = odd_even.copied().collect();
+            },
+        );
+    }
+
+    #[test]
+    fn test_middle_and_nth_from_end() {
+        for len in 0..10 {
+            let list: ListNodeIndex<i32> = (0..len).collect();
+            assert_eq!(list.middle(), (len > 0).then_some(&(len / 2)));
+            for k in 0..12 {
+                let expected = (k < len).then(|| len - 1 - k);
+                assert_eq!(list.nth_from_end(k as usize).copied(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_palindrome() {
+        let cases: [&[i32]; 7] = [&[], &[1], &[1, 1], &[1, 2], &[1, 2, 1], &[1, 2, 2, 1], &[1, 2, 3, 1]];
+        for case in cases {
+            let mut list: ListNodeIndex<i32> = case.iter().copied().collect();
+            let expected = case.iter().eq(case.iter().rev());
+            assert_eq!(list.is_palindrome(), expected, "{case:?}");
+            // Список восстановлен после временного разворота
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), case);
+        }
+    }
+
+    #[test]
+    fn test_split_halves() {
+        for len in 0..10 {
+            let list: ListNodeIndex<i32> = (0..len).collect();
+            let (mut first, second) = list.split_halves();
+            assert_eq!(first.iter().copied().collect::<Vec<_>>(), (0..len / 2).collect::<Vec<_>>());
+            assert_eq!(second.iter().copied().collect::<Vec<_>>(), (len / 2..len).collect::<Vec<_>>());
+            assert_eq!((first.len(), second.len()), (len as usize / 2, (len - len / 2) as usize));
+
+            first.extend([100]);
+            assert_eq!(first.len(), len as usize / 2 + 1);
+        }
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let a: ListNodeIndex<i32> = [1, 3, 5, 7].into_iter().collect();
+        let mut b: ListNodeIndex<i32> = [0, 2, 3, 8].into_iter().collect();
+        b.pop_head();
+        let merged = ListNodeIndex::merge_sorted(a, b);
+        assert_eq!(merged.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 3, 5, 7, 8]);
+        assert_eq!(merged.len(), 7);
+        // Переносятся только живые элементы b, его свободный слот не копируется
+        assert_eq!((merged.nodes.len(), merged.free_list.len()), (7, 0));
+
+        let empty = ListNodeIndex::<i32>::merge_sorted(ListNodeIndex::new(), ListNodeIndex::new());
+        assert!(empty.is_empty());
+
+        // При равных ключах элементы из первого списка идут раньше
+        let a: ListNodeIndex<(i32, char)> = [(1, 'a'), (2, 'a'), (2, 'b')].into_iter().collect();
+        let b: ListNodeIndex<(i32, char)> = [(1, 'c'), (2, 'c')].into_iter().collect();
+        let merged = ListNodeIndex::merge_sorted_by_key(a, b, |&(key, _)| key);
+        let tags: Vec<_> = merged.iter().map(|&(_, tag)| tag).collect();
+        assert_eq!(tags, vec!['a', 'c', 'a', 'b', 'c']);
+    }
+
+    #[test]
+    fn test_merge_k_sorted() {
+        let mut lists = Vec::new();
+        let mut model = Vec::new();
+        for source in 0..7 {
+            let mut items: Vec<_> = (0..source * 3).map(|i| ((i * 7 + source) % 10, source)).collect();
+            items.sort_by_key(|&(key, _)| key);
+            model.extend(items.iter().copied());
+            lists.push(items.into_iter().collect::<ListNodeIndex<_>>());
+        }
+        // Устойчивость: при равных ключах порядок как у конкатенации списков
+        model.sort_by_key(|&(key, _)| key);
+
+        let merged = ListNodeIndex::merge_k_sorted_by_key(lists, |&(key, _)| key);
+        assert_eq!(merged.iter().copied().collect::<Vec<_>>(), model);
+        assert_eq!(merged.len(), model.len());
+        merged.check_invariants();
+
+        let lists: Vec<ListNodeIndex<i32>> = vec![ListNodeIndex::new(), (0..3).collect(), ListNodeIndex::new()];
+        let merged = ListNodeIndex::merge_k_sorted(lists);
+        assert_eq!(merged.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert!(ListNodeIndex::<i32>::merge_k_sorted(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_retain_and_extract_if() {
+        let mut list: ListNodeIndex<i32> = (1..=10).collect();
+        list.retain(|&data| data % 3 != 0);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 4, 5, 7, 8, 10]);
+        assert_eq!(list.free_list.len(), 3);
+
+        list.retain_mut(|data| {
+            *data *= 10;
+            *data != 10
+        });
+        let big: Vec<_> = list.extract_if(|data| *data > 60).collect();
+        assert_eq!(big, vec![70, 80, 100]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![20, 40, 50]);
+
+        // Освободившиеся слоты используются повторно
+        let slots = list.nodes.len();
+        list.extend([1, 2, 3]);
+        assert_eq!(list.nodes.len(), slots);
+        assert_eq!(list.len(), 6);
+
+        // Без проверок закольцованный список обходится не дальше len узлов
+        let mut list: ListNodeIndex<i32> =
+            ListNodeIndex::from_iter(0..4).with_invariant_policy(InvariantPolicy::Never);
+        assert_eq!(list.try_make_cycle_at(1), Ok(()));
+        list.retain(|_| true);
+        assert_eq!(list.extract_if(|data| *data == 2).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(list.try_make_cycle_at(2), Ok(()));
+        assert_eq!(list.find_cycle().map(|info| info.entry_position), Some(2));
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut list: ListNodeIndex<i32> = (0..8).collect();
+        let drained: Vec<_> = list.drain(2..5).collect();
+        assert_eq!(drained, vec![2, 3, 4]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 5, 6, 7]);
+
+        let mut drain = list.drain(..=1);
+        assert_eq!(drain.next(), Some(0));
+        drop(drain);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![5, 6, 7]);
+        assert_eq!(list.free_list.len(), 5);
+
+        assert_eq!(list.drain(..).len(), 3);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_exact_size_iterators() {
+        let mut list: ListNodeIndex<i32> = (1..=5).collect();
+        list.remove_at(2);
+        assert_eq!(list.len(), 4);
+
+        let mut iter = list.iter();
+        iter.next();
+        assert_eq!(iter.len(), 3);
+
+        let mut iter = list.iter_mut();
+        iter.next();
+        iter.next();
+        assert_eq!(iter.len(), 2);
+
+        let (a, b) = list.divide_at(1).unwrap();
+        assert_eq!(a.join(b).len(), 4);
+    }
+
+    #[test]
+    fn test_iter_mut_follows_links() {
+        // Освобождённый слот занят новой головой, поэтому порядок узлов не совпадает с порядком
+        // слотов
+        let mut list: ListNodeIndex<i32> = (1..=4).collect();
+        assert_eq!(list.remove_at(2), Some(3));
+        list.push_head(0);
+        for data in &mut list {
+            *data *= 10;
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 10, 20, 40]);
+
+        // Без проверок закольцованный список отдаёт ровно len элементов
+        let mut list: ListNodeIndex<i32> =
+            ListNodeIndex::from_iter(0..4).with_invariant_policy(InvariantPolicy::Never);
+        list.make_cycle_at(2);
+        assert_eq!(list.iter_mut().map(|data| *data).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_stale_handle_after_slot_reuse() {
+        let mut list = ListNodeIndex::new();
+        let first = list.insert_head(1);
+        assert_eq!(list.pop_head(), Some(1));
+        assert_eq!(list.get(first), None);
+
+        // Новый элемент занимает тот же слот, но старый дескриптор к нему не подходит
+        let second = list.insert_head(2);
+        assert_eq!(second.index, first.index);
+        assert_eq!(list.get(first), None);
+        assert_eq!(list.get_mut(first), None);
+        assert_eq!(list.remove(first), None);
+        assert_eq!(list.insert_after(first, 3), Err(ListError::StaleHandle));
+        assert_eq!(list.get(second), Some(&2));
+    }
+
+    #[test]
+    fn test_handles() {
+        let mut list = ListNodeIndex::new();
+        let tail = list.insert_head(3);
+        let head = list.insert_head(1);
+        let middle = list.insert_after(head, 2).unwrap();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        *list.get_mut(middle).unwrap() = 20;
+        // Перевязка узлов не трогает слоты, дескрипторы остаются верными
+        list.reverse();
+        assert_eq!(list.get(middle), Some(&20));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![3, 20, 1]);
+
+        assert_eq!(list.remove(middle), Some(20));
+        assert_eq!(list.remove(middle), None);
+        assert_eq!(list.remove(tail), Some(3));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(list.len(), 1);
+
+        let last = list.insert_after(head, 4).unwrap();
+        assert_ne!(last, middle);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 4]);
+    }
+
+    #[test]
+    fn test_handles_after_divide() {
+        let mut list = ListNodeIndex::new();
+        let third = list.insert_head(3);
+        let second = list.insert_head(2);
+        let first = list.insert_head(1);
+
+        let (mut left, mut right) = list.divide_at(1).expect("position is in bounds");
+        assert_eq!(left.get(first), Some(&1));
+        for handle in [second, third] {
+            assert_eq!(left.get(handle), None);
+            assert_eq!(right.get(handle), None);
+            assert_eq!(right.insert_after(handle, 9), Err(ListError::StaleHandle));
+        }
+        assert_eq!(right.get(first), None);
+        assert_eq!(right.remove(third), None);
+        assert_eq!(right.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+
+        // Новые дескрипторы работают в обоих списках
+        let zero = right.insert_head(0);
+        assert_eq!(right.insert_after(zero, 1).map(|one| right.get(one).copied()), Ok(Some(1)));
+        assert_eq!(right.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        let four = left.insert_after(first, 4).expect("handle is valid");
+        assert_eq!(left.remove(four), Some(4));
+
+        let mut list = ListNodeIndex::new();
+        let handles: Vec<_> = (0..4).map(|value| list.insert_head(value)).collect();
+        let (first_half, second_half) = list.split_halves();
+        assert_eq!(first_half.get(handles[3]), Some(&3));
+        assert_eq!(second_half.get(handles[0]), None);
+        assert_eq!(second_half.get(handles[1]), None);
+    }
+
+    #[test]
+    fn test_handles_after_join() {
+        let mut a = ListNodeIndex::new();
+        let a1 = a.insert_head("a1");
+        let a0 = a.insert_head("a0");
+        let mut b = ListNodeIndex::new();
+        let b0 = b.insert_head("b0");
+
+        // Элементы b переезжают в слоты a, но дескрипторы b к a не подходят
+        let mut joined = a.join(b);
+        assert_eq!(joined.get(a0), Some(&"a0"));
+        assert_eq!(joined.get(a1), Some(&"a1"));
+        assert_eq!(joined.get(b0), None);
+        assert_eq!(joined.insert_after(b0, "x"), Err(ListError::StaleHandle));
+        assert_eq!(joined.remove(b0), None);
+        assert_eq!(joined.iter().copied().collect::<Vec<_>>(), vec!["a0", "a1", "b0"]);
+
+        // Копия — другой список
+        let copy = joined.clone();
+        assert_eq!(copy.get(a0), None);
+    }
+
+    #[test]
+    fn test_divide_and_join_reuse_slots() {
+        let mut list: ListNodeIndex<i32> = (0..10).collect();
+        for _ in 0..10 {
+            let (left, right) = list.divide_at(5).expect("position is in bounds");
+            assert_eq!(right.nodes.len(), 5);
+            list = left.join(right);
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+        assert_eq!((list.nodes.len(), list.free_list.len()), (10, 0));
+    }
+
+    linked_list::conformance_tests!(
+        ListNodeIndex<i32>,
+        make_cycle = ListNodeIndex::make_cycle_at
+    );
 }
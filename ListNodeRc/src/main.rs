@@ -1,6 +1,17 @@
 #![allow(unused)]
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering as AtomicOrdering},
+};
+
+use linked_list::{
+    dot_escape, CycleInfo, InvariantChecks, InvariantPolicy, LinkedList, ListError,
+};
 
 type Link<T> = Option<Rc<NodeRc<T>>>;
 
@@ -20,218 +31,588 @@ impl<T> NodeRc<T> {
     }
 }
 
+// Узлы сравниваются, хешируются и печатаются по данным, без учёта связей
+impl<T: PartialEq> PartialEq for NodeRc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl<T: Eq> Eq for NodeRc<T> {}
+
+impl<T: PartialOrd> PartialOrd for NodeRc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.data.partial_cmp(&other.data)
+    }
+}
+
+impl<T: Ord> Ord for NodeRc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.data.cmp(&other.data)
+    }
+}
+
+impl<T: Hash> Hash for NodeRc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for NodeRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.data.fmt(f)
+    }
+}
+
 struct ListNodeRc<T> {
     head: Link<T>,
+    len: usize,
+    sharing: Sharing<T>,
+    invariants: InvariantChecks,
+    // Пока id есть, узлы списка не видны другим версиям, а узлы, ушедшие из списка, уносят
+    // старый id. Handle с тем же id правит свой узел за O(1), остальные ищутся от головы
+    id: Cell<Option<u64>>,
 }
 
-struct IntoIterRc<T>(ListNodeRc<T>);
+static NEXT_LIST_ID: AtomicU64 = AtomicU64::new(0);
+
+fn next_list_id() -> Option<u64> {
+    Some(NEXT_LIST_ID.fetch_add(1, AtomicOrdering::Relaxed))
+}
+
+// Что делать, когда изменяемый узел делят другие версии списка или handle
+enum Sharing<T> {
+    // Изменение отказывает с ListError::Shared
+    Strict,
+    // Общее начало списка копируется, остальные владельцы продолжают видеть старые узлы
+    CopyOnWrite(fn(&T) -> T),
+}
+
+impl<T> Clone for Sharing<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Sharing<T> {}
+
+// Узлы, общие с другими версиями списка, остаются им, а данные из них копируются функцией copy.
+// Другие владельцы при этом не меняются. Без copy общий узел отдать нельзя
+struct IntoIterRc<T> {
+    list: ListNodeRc<T>,
+    copy: Option<fn(&T) -> T>,
+}
 
 impl<T> Iterator for IntoIterRc<T> {
     type Item = T;
 
+    // Счётчик не даёт уйти в бесконечный цикл, если список закольцован
     fn next(&mut self) -> Option<Self::Item> {
-        let head = self.0.head.take()?;
+        if self.list.len == 0 {
+            self.list.head = None;
+            return None;
+        }
+        let head = self.list.head.take()?;
+        self.list.len -= 1;
         match Rc::try_unwrap(head) {
             Ok(node) => {
-                self.0.head = node.next.into_inner();
+                self.list.head = node.next.into_inner();
                 Some(node.data)
             }
-            Err(_) => panic!("Multiple references to node in IntoIterRc"),
+            Err(shared) => {
+                let copy = self.copy.expect("strict into_iter checked that nodes are not shared");
+                self.list.head = shared.next_node();
+                Some(copy(&shared.data))
+            }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
 }
 
+impl<T> ExactSizeIterator for IntoIterRc<T> {}
+
 struct IterRc<T> {
     next: Option<Rc<NodeRc<T>>>,
+    len: usize,
 }
 
 impl<T> Iterator for IterRc<T> {
     type Item = Rc<NodeRc<T>>;
 
+    // Счётчик не даёт уйти в бесконечный цикл, если список закольцован
     fn next(&mut self) -> Option<Self::Item> {
-        self.next.take().map(|node| {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        self.next.take().inspect(|node| {
             let next = node.next.borrow().clone();
             self.next = next;
-            node
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
 }
 
-// Iterator for IterMutRc реализовать нельзя, т.к. список построен на Rc, а Rc подразумевает совместное владение (&mut должен быть только 1)
-struct IterMutRc<T> {
-    next: Link<T>,
+impl<T> ExactSizeIterator for IterRc<T> {}
+
+// &mut к данным выдаётся через Rc::get_mut, поэтому итератор создаётся, только если у каждого
+// узла единственный владелец — предыдущий узел или голова списка
+struct IterMutRc<'a, T> {
+    next: Option<&'a mut NodeRc<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMutRc<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let NodeRc { data, next } = self.next.take()?;
+        self.next = next
+            .get_mut()
+            .as_mut()
+            .map(|node| Rc::get_mut(node).expect("iter_mut checked unique ownership"));
+        self.len -= 1;
+        Some(data)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
 }
 
-impl<T> Default for ListNodeRc<T> {
+impl<T> ExactSizeIterator for IterMutRc<'_, T> {}
+
+// Ссылка на узел долгоживущего списка и id списка на момент выдачи. Handle удерживает узел,
+// поэтому в строгом режиме remove_after откажется удалять узел, на который он указывает
+struct NodeHandle<T>(Rc<NodeRc<T>>, Option<u64>);
+
+// Строка отчёта sharing_report: узел, который держит кто-то кроме предыдущего узла или головы.
+// Отчёт сам держит узел, strong_count посчитан без этой ссылки
+struct SharedNode<T> {
+    position: usize,
+    node: Rc<NodeRc<T>>,
+    strong_count: usize,
+}
+
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        NodeHandle(Rc::clone(&self.0), self.1)
+    }
+}
+
+// По умолчанию общие узлы копируются при записи. Данным без Clone копировать нечем, для них
+// есть строгий new_strict
+impl<T: Clone> Default for ListNodeRc<T> {
     fn default() -> Self {
-        Self { head: None }
+        Self::new_strict().copy_on_write()
     }
 }
 
 impl<T> ListNodeRc<T> {
-    fn is_empty(&self) -> bool {
-        self.head.is_none()
+    // Проверка выполняется, только если этого требует политика списка
+    fn try_check_invariants(&self) -> Result<(), ListError> {
+        if self.invariants.due() && self.has_cycle() {
+            return Err(ListError::CycleDetected);
+        }
+        Ok(())
     }
 
     fn check_invariants(&self) {
-        if self.has_cycle() {
-            panic!("Cycle detected in linked list!");
+        if let Err(err) = self.try_check_invariants() {
+            match self.find_cycle() {
+                Some(info) => panic!("{err}: {info:?}"),
+                None => panic!("{err}"),
+            }
+        }
+    }
+
+    // Вызывать только для списка без цикла
+    fn out_of_bounds(&self, position: usize) -> ListError {
+        ListError::OutOfBounds {
+            position,
+            len: self.len,
         }
     }
 
     fn get_node_at(&self, position: usize) -> Option<Rc<NodeRc<T>>> {
-        self.iter_nodes().nth(position)
+        self.iter().nth(position)
     }
 
-    fn into_iter(self) -> IntoIterRc<T> {
-        IntoIterRc(self)
+    fn handle_at(&self, position: usize) -> Option<NodeHandle<T>> {
+        let node = self.get_node_at(position)?;
+        Some(NodeHandle(node, self.id.get()))
     }
 
-    fn iter(&self) -> IterRc<T> {
-        IterRc {
-            next: self.head.as_ref().map(Rc::clone),
-        }
+    fn value<'a>(&self, handle: &'a NodeHandle<T>) -> &'a T {
+        &handle.0.data
     }
 
-    fn iter_mut(&mut self) -> IterMutRc<T> {
-        IterMutRc {
-            next: self.head.as_ref().map(Rc::clone),
-        }
+    fn next(&self, handle: &NodeHandle<T>) -> Option<NodeHandle<T>> {
+        handle.0.next_node().map(|node| NodeHandle(node, handle.1))
     }
 
-    fn new() -> Self {
-        Self::default()
+    // Узлы стали видны другой версии списка: handle больше не правят их напрямую
+    fn forget_handles(&self) {
+        self.id.set(None);
     }
 
-    fn pop_head(&mut self) -> Option<T> {
-        self.check_invariants();
+    // Узел, который мог держать handle, ушёл из списка: старые handle ищутся от головы
+    fn renew_id(&self) {
+        if self.id.get().is_some() {
+            self.id.set(next_list_id());
+        }
+    }
 
-        let old_head = self.head.take()?;
+    // Позиция узла handle; StaleHandle, если узел не из этого списка
+    fn position_of(&self, handle: &NodeHandle<T>) -> Result<usize, ListError> {
+        self.iter()
+            .position(|node| Rc::ptr_eq(&node, &handle.0))
+            .ok_or(ListError::StaleHandle)
+    }
 
-        self.head = old_head.next_node();
+    // Делает собственными узлы до position включительно и возвращает узел position. Ссылка
+    // handle на этот узел владельцем не считается; если узел всё же копируется, handle остаётся
+    // у старой версии
+    fn own_node_at(
+        &mut self,
+        position: usize,
+        handle: &NodeHandle<T>,
+    ) -> Result<Rc<NodeRc<T>>, ListError> {
+        self.unshare_prefix(position)?;
+
+        let sharing = self.sharing;
+        let mut link = &mut self.head;
+        for _ in 0..position {
+            let node = link.as_mut().expect("position is in bounds");
+            link = Rc::get_mut(node).expect("prefix is unique").next.get_mut();
+        }
+        let node = link.as_mut().expect("position is in bounds");
+        // Одна ссылка у предыдущего узла или головы и одна у handle
+        if Rc::strong_count(node) > 2 {
+            let Sharing::CopyOnWrite(copy) = sharing else {
+                return Err(ListError::Shared);
+            };
+            *node = Rc::new(NodeRc {
+                data: copy(&node.data),
+                next: RefCell::new(node.next_node()),
+            });
+            let node = Rc::clone(node);
+            self.renew_id();
+            return Ok(node);
+        }
+        Ok(Rc::clone(node))
+    }
 
-        self.check_invariants();
+    // Handle с id списка указывает на узел этого списка, и другие версии этот узел не видят
+    fn owns(&self, handle: &NodeHandle<T>) -> bool {
+        handle.1.is_some() && handle.1 == self.id.get()
+    }
 
-        match Rc::try_unwrap(old_head) {
-            Ok(node) => Some(node.data),
-            Err(_) => panic!("Cannot pop head"),
+    // Узел, который правит handle, и его позиция, если её пришлось искать. Свой узел handle
+    // берётся за O(1). Чужой handle ищется от головы; если узлы списка видны другим версиям,
+    // начало списка до узла ещё и становится собственным
+    fn handle_node(
+        &mut self,
+        handle: &NodeHandle<T>,
+    ) -> Result<(Rc<NodeRc<T>>, Option<usize>), ListError> {
+        if self.owns(handle) {
+            return Ok((Rc::clone(&handle.0), None));
         }
+        let position = self.position_of(handle)?;
+        if self.id.get().is_some() {
+            return Ok((Rc::clone(&handle.0), Some(position)));
+        }
+        Ok((self.own_node_at(position, handle)?, Some(position)))
     }
 
-    fn push_head(&mut self, data: T) {
-        self.check_invariants();
+    // Возвращает handle нового узла
+    fn insert_after(
+        &mut self,
+        handle: &NodeHandle<T>,
+        data: T,
+    ) -> Result<NodeHandle<T>, ListError> {
+        self.try_check_invariants()?;
+        let (prev_node, _) = self.handle_node(handle)?;
 
-        let new_head = Rc::new(NodeRc {
+        let node = Rc::new(NodeRc {
             data,
-            next: RefCell::new(self.head.take()),
+            next: RefCell::new(prev_node.next_node()),
         });
+        prev_node.set_next(Some(Rc::clone(&node)));
+        self.len += 1;
+        Ok(NodeHandle(node, self.id.get()))
+    }
 
-        self.head = Some(new_head);
+    // Ok(None), если за handle узлов нет. Если следующий узел удерживает другой handle или
+    // версия списка, строгий режим отказывает с Shared, а копирование при записи отдаёт копию
+    // данных и оставляет узел другим владельцам
+    fn remove_after(&mut self, handle: &NodeHandle<T>) -> Result<Option<T>, ListError> {
+        self.try_check_invariants()?;
+        let (prev_node, position) = self.handle_node(handle)?;
+        // Последний узел закольцованного списка указывает на вход в цикл
+        if position.is_some_and(|position| position + 1 == self.len) {
+            return Ok(None);
+        }
+        let Some(next) = prev_node.next_node() else {
+            return Ok(None);
+        };
+        // Одна ссылка у узла handle и одна локальная
+        if let (Sharing::Strict, true) = (self.sharing, Rc::strong_count(&next) > 2) {
+            return Err(ListError::Shared);
+        }
+        drop(next);
+
+        let node_to_remove = prev_node.next_node().expect("position is not the last one");
+        prev_node.set_next(node_to_remove.next_node());
+        self.len -= 1;
+
+        match Rc::try_unwrap(node_to_remove) {
+            Ok(node) => Ok(Some(node.data)),
+            Err(shared) => match self.sharing {
+                Sharing::CopyOnWrite(copy) => {
+                    self.renew_id();
+                    Ok(Some(copy(&shared.data)))
+                }
+                Sharing::Strict => unreachable!("strict mode checked the node above"),
+            },
+        }
+    }
 
-        self.check_invariants();
+    fn iter(&self) -> IterRc<T> {
+        IterRc {
+            next: self.head.as_ref().map(Rc::clone),
+            len: self.len,
+        }
     }
 
-    fn join(self, mut other: Self) -> Self {
-        self.check_invariants();
-        other.check_invariants();
+    // В строгом режиме Shared, если хотя бы один узел удерживается снаружи; тогда ни один
+    // элемент не меняется
+    fn iter_mut(&mut self) -> Result<IterMutRc<'_, T>, ListError> {
+        self.try_check_invariants()?;
+        self.unshare_prefix(self.len)?;
+
+        Ok(IterMutRc {
+            len: self.len,
+            next: self
+                .head
+                .as_mut()
+                .map(|node| Rc::get_mut(node).expect("unique ownership checked above")),
+        })
+    }
 
-        if other.is_empty() {
-            return self;
-        }
+    fn for_each_mut<F: FnMut(&mut T)>(&mut self, f: F) -> Result<(), ListError> {
+        self.iter_mut()?.for_each(f);
+        Ok(())
+    }
 
-        if self.is_empty() {
-            return other;
+    // Через общий узел перед position другие владельцы видят и сам узел position, поэтому
+    // собственным должно быть всё начало списка до него; хвост после него может оставаться общим
+    fn update_at<F: FnOnce(&mut T)>(&mut self, position: usize, f: F) -> Result<(), ListError> {
+        self.try_check_invariants()?;
+        if self.head.is_none() {
+            return Err(ListError::Empty);
+        }
+        let out_of_bounds = self.out_of_bounds(position);
+        if position >= self.len {
+            return Err(out_of_bounds);
         }
+        self.unshare_prefix(position + 1)?;
 
-        if let Some(last_node) = self.iter_nodes().last() {
-            last_node.set_next(other.head.take());
+        if position == 0 {
+            let head = self.head.as_mut().expect("list is not empty");
+            let node = Rc::get_mut(head).ok_or(ListError::Shared)?;
+            f(&mut node.data);
+            return Ok(());
         }
 
-        self.check_invariants();
-        self
+        let prev_node = self.get_node_at(position - 1).ok_or(out_of_bounds)?;
+        let mut link = prev_node.next.borrow_mut();
+        let node = link.as_mut().ok_or(out_of_bounds)?;
+        let node = Rc::get_mut(node).ok_or(ListError::Shared)?;
+        f(&mut node.data);
+        Ok(())
     }
 
-    fn divide_at(&mut self, position: usize) -> Option<(Self, Self)> {
-        self.check_invariants();
-        if self.is_empty() {
-            return None;
-        }
+    fn new() -> Self
+    where
+        T: Clone,
+    {
+        Self::default()
+    }
 
-        if position == 0 {
-            return Some((Self::new(), std::mem::take(self)));
+    fn new_strict() -> Self {
+        Self {
+            head: None,
+            len: 0,
+            sharing: Sharing::Strict,
+            invariants: InvariantChecks::default(),
+            id: Cell::new(next_list_id()),
         }
+    }
 
-        let prev_node = self.get_node_at(position - 1)?;
+    // Пустой список с тем же режимом и политикой
+    fn empty_like(&self) -> Self {
+        Self {
+            head: None,
+            len: 0,
+            sharing: self.sharing,
+            invariants: self.invariants.clone(),
+            id: Cell::new(next_list_id()),
+        }
+    }
 
-        let head2 = prev_node.next_node();
-        prev_node.set_next(None);
+    // Как into_iter, но данные общих узлов клонируются и в строгом режиме
+    fn into_iter_cloned(self) -> IntoIterRc<T>
+    where
+        T: Clone,
+    {
+        self.check_invariants();
+        IntoIterRc {
+            list: self,
+            copy: Some(T::clone),
+        }
+    }
 
-        let list1 = ListNodeRc {
-            head: self.head.take(),
+    // Строгий режим отказывает с Shared, если хоть один узел делят другие версии или handle;
+    // у закольцованного списка вход в цикл держат два узла, и он тоже считается общим
+    fn try_into_iter(self) -> Result<IntoIterRc<T>, ListError> {
+        self.try_check_invariants()?;
+        let copy = match self.sharing {
+            Sharing::Strict => {
+                if self.nodes_with_owners(None).any(|(_, owners)| owners.is_some()) {
+                    return Err(ListError::Shared);
+                }
+                None
+            }
+            Sharing::CopyOnWrite(copy) => Some(copy),
         };
+        Ok(IntoIterRc { list: self, copy })
+    }
 
-        let list2 = ListNodeRc { head: head2 };
+    // Изменения общих узлов копируют их, а не отказывают
+    fn copy_on_write(mut self) -> Self
+    where
+        T: Clone,
+    {
+        self.sharing = Sharing::CopyOnWrite(T::clone);
+        self
+    }
 
-        self.check_invariants();
-        Some((list1, list2))
+    fn strict(mut self) -> Self {
+        self.sharing = Sharing::Strict;
+        self
     }
 
-    fn append_at(&mut self, position: usize, data: T) {
-        self.check_invariants();
+    fn with_invariant_policy(mut self, policy: InvariantPolicy) -> Self {
+        self.invariants = InvariantChecks::new(policy);
+        self
+    }
 
-        if position == 0 {
-            self.push_head(data);
-            return;
+    // Первые count узлов становятся собственными узлами списка. Если узел общий, строгий режим
+    // отказывает, ничего не меняя, а копирование при записи копирует его; после копии следующий
+    // узел тоже становится общим, так что копируется ровно общее начало. Вызывать для списка
+    // без цикла
+    fn unshare_prefix(&mut self, count: usize) -> Result<(), ListError> {
+        let sharing = self.sharing;
+        let mut copied = false;
+        let mut link = &mut self.head;
+        for _ in 0..count {
+            let Some(node) = link else {
+                break;
+            };
+            if Rc::strong_count(node) > 1 {
+                let Sharing::CopyOnWrite(copy) = sharing else {
+                    return Err(ListError::Shared);
+                };
+                *node = Rc::new(NodeRc {
+                    data: copy(&node.data),
+                    next: RefCell::new(node.next_node()),
+                });
+                copied = true;
+            }
+            link = Rc::get_mut(node).expect("node is unique").next.get_mut();
         }
+        if copied {
+            self.renew_id();
+        }
+        Ok(())
+    }
 
-        let prev_node = self.get_node_at(position - 1).expect("append_at failed");
-
-        let new_node = Rc::new(NodeRc {
+    // Персистентные операции: исходный список не меняется, новая версия делит с ним узлы
+    fn cons(&self, data: T) -> Self {
+        self.forget_handles();
+        let head = Rc::new(NodeRc {
             data,
-            next: RefCell::new(prev_node.next_node()),
+            next: RefCell::new(self.head.clone()),
         });
-
-        *prev_node.next.borrow_mut() = Some(new_node);
-
-        self.check_invariants();
+        Self {
+            head: Some(head),
+            len: self.len + 1,
+            sharing: self.sharing,
+            invariants: self.invariants.clone(),
+            id: Cell::new(None),
+        }
     }
 
-    fn remove_at(&mut self, position: usize) {
-        self.check_invariants();
+    // Хвост пустого списка — пустой список
+    fn tail(&self) -> Self {
+        match &self.head {
+            Some(head) => {
+                self.forget_handles();
+                Self {
+                    head: head.next_node(),
+                    len: self.len - 1,
+                    sharing: self.sharing,
+                    invariants: self.invariants.clone(),
+                    id: Cell::new(None),
+                }
+            }
+            None => self.empty_like(),
+        }
+    }
 
-        if self.is_empty() {
-            return;
+    // Последний узел начинает указывать на узел с индексом position, позиция считается с нуля
+    fn try_make_cycle_at(&mut self, position: usize) -> Result<(), ListError> {
+        self.try_check_invariants()?;
+        if self.head.is_none() {
+            return Err(ListError::Empty);
         }
 
-        let prev_node = self.get_node_at(position - 1).expect("remove_at failed");
+        if position >= self.len {
+            return Err(self.out_of_bounds(position));
+        }
+        self.unshare_prefix(self.len)?;
 
-        let node_to_remove = prev_node.next_node();
+        let target_node = self.get_node_at(position).expect("position is in bounds");
 
-        if let Some(node) = node_to_remove {
-            let next_node = node.next_node();
-            prev_node.set_next(next_node);
-        }
+        let last_node = self.iter().last().expect("list is not empty");
 
-        self.check_invariants();
+        // Последний узел теперь указывает не в конец списка, handle ищутся от головы
+        self.forget_handles();
+        last_node.set_next(Some(target_node));
+        Ok(())
     }
 
+    // Как в исходной версии, позиция считается с единицы: make_cycle_at(1) замыкает последний
+    // узел на голову. Пустой список не меняется
     fn make_cycle_at(&mut self, position: usize) {
-        self.check_invariants();
-        if self.is_empty() {
+        if self.head.is_none() {
             return;
         }
-
-        let target_node = self
-            .get_node_at(position - 1)
-            .expect("make_cycle_at failed");
-
-        let last_node = self.iter_nodes().last().expect("make_cycle_at failed");
-
-        last_node.set_next(Some(Rc::clone(&target_node)));
+        let index = position.checked_sub(1).expect("make_cycle_at positions start at 1");
+        if let Err(err) = self.try_make_cycle_at(index) {
+            panic!("make_cycle_at failed: {err}");
+        }
     }
 
     fn has_cycle(&self) -> bool {
-        if self.is_empty() {
+        if self.head.is_none() {
             return false;
         }
 
@@ -257,6 +638,354 @@ impl<T> ListNodeRc<T> {
         false
     }
 
+    // Алгоритм Флойда: после встречи указателей внутри цикла один возвращается в голову, и оба
+    // идут по шагу до входа в цикл
+    fn find_cycle(&self) -> Option<CycleInfo> {
+        let head = self.head.clone()?;
+
+        let (mut slow, mut fast) = (Rc::clone(&head), Rc::clone(&head));
+        loop {
+            slow = slow.next_node()?;
+            fast = fast.next_node()?.next_node()?;
+            if Rc::ptr_eq(&slow, &fast) {
+                break;
+            }
+        }
+
+        let mut entry = head;
+        let mut entry_position = 0;
+        while !Rc::ptr_eq(&entry, &slow) {
+            entry = entry.next_node().expect("cycle has no end");
+            slow = slow.next_node().expect("cycle has no end");
+            entry_position += 1;
+        }
+
+        let mut cycle_len = 1;
+        let mut node = entry.next_node().expect("cycle has no end");
+        while !Rc::ptr_eq(&node, &entry) {
+            node = node.next_node().expect("cycle has no end");
+            cycle_len += 1;
+        }
+
+        Some(CycleInfo {
+            entry_position,
+            cycle_len,
+            tail_position: entry_position + cycle_len - 1,
+        })
+    }
+
+    // Обрезает обратную ссылку, которую создал make_cycle_at; возвращает, где был цикл
+    fn break_cycle(&mut self) -> Option<CycleInfo> {
+        let info = self.find_cycle()?;
+        let tail = self
+            .get_node_at(info.tail_position)
+            .expect("tail is inside the list");
+        tail.set_next(None);
+        Some(info)
+    }
+
+    // Узлы в порядке списка; для общих узлов — число владельцев без учёта самого дампа. Узел
+    // общий, если его держит кто-то кроме предыдущего узла или головы; вход в цикл держит ещё
+    // и последний узел
+    fn dump_nodes(&self, cycle: Option<CycleInfo>) -> Vec<(Rc<NodeRc<T>>, Option<usize>)> {
+        self.nodes_with_owners(cycle).collect()
+    }
+
+    // Владельцы узла считаются до того, как обход возьмёт ссылку на следующий узел: иначе
+    // у петли на себя лишней оказалась бы и эта ссылка
+    fn nodes_with_owners(
+        &self,
+        cycle: Option<CycleInfo>,
+    ) -> impl Iterator<Item = (Rc<NodeRc<T>>, Option<usize>)> {
+        let mut next = self.head.clone();
+        (0..self.len).map_while(move |position| {
+            let node = next.take()?;
+            let owners = Rc::strong_count(&node) - 1;
+            let internal = match cycle {
+                Some(info) if info.entry_position == position => 2,
+                _ => 1,
+            };
+            next = node.next_node();
+            Some((node, (owners > internal).then_some(owners)))
+        })
+    }
+
+    // Граф для Graphviz: узлы подписаны позицией и адресом Rc, общие узлы закрашены, обратная
+    // ссылка цикла пунктирная
+    fn write_dot(&self, out: &mut impl fmt::Write) -> fmt::Result
+    where
+        T: fmt::Debug,
+    {
+        let cycle = self.find_cycle();
+        let nodes = self.dump_nodes(cycle);
+        writeln!(out, "digraph ListNodeRc {{")?;
+        writeln!(out, "    rankdir=LR;")?;
+        writeln!(out, "    node [shape=box];")?;
+        writeln!(out, "    head [shape=plaintext];")?;
+
+        let mut prev = String::from("head");
+        for (position, (node, shared)) in nodes.iter().enumerate() {
+            let label = format!("#{position} {:p}\n{:?}", Rc::as_ptr(node), node.data);
+            let style = match shared {
+                Some(owners) => {
+                    format!(", style=filled, fillcolor=lightblue, xlabel=\"rc={owners}\"")
+                }
+                None => String::new(),
+            };
+            writeln!(out, "    n{position} [label=\"{}\"{style}];", dot_escape(&label))?;
+            writeln!(out, "    {prev} -> n{position};")?;
+            prev = format!("n{position}");
+        }
+        if let Some(info) = cycle {
+            writeln!(
+                out,
+                "    n{} -> n{} [style=dashed, color=red, label=\"cycle\"];",
+                info.tail_position, info.entry_position
+            )?;
+        }
+        writeln!(out, "}}")
+    }
+
+    fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut out = String::new();
+        self.write_dot(&mut out).expect("writing to a String does not fail");
+        out
+    }
+
+    // Строка на узел: позиция, адрес, данные, число владельцев общего узла и куда ведёт next
+    fn write_ascii_diagram(&self, out: &mut impl fmt::Write) -> fmt::Result
+    where
+        T: fmt::Debug,
+    {
+        let cycle = self.find_cycle();
+        let nodes = self.dump_nodes(cycle);
+        let first = if nodes.is_empty() { "None" } else { "#0" };
+        writeln!(out, "head -> {first}")?;
+        for (position, (node, shared)) in nodes.iter().enumerate() {
+            write!(out, "#{position} {:p} {:?}", Rc::as_ptr(node), node.data)?;
+            if let Some(owners) = shared {
+                write!(out, " (rc={owners})")?;
+            }
+            match cycle {
+                _ if position + 1 < nodes.len() => writeln!(out, " -> #{}", position + 1)?,
+                Some(info) => writeln!(out, " -> #{} (cycle)", info.entry_position)?,
+                None => writeln!(out, " -> None")?,
+            }
+        }
+        Ok(())
+    }
+
+    fn to_ascii_diagram(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut out = String::new();
+        self.write_ascii_diagram(&mut out)
+            .expect("writing to a String does not fail");
+        out
+    }
+
+    fn sharing_report(&self) -> Vec<SharedNode<T>> {
+        self.dump_nodes(self.find_cycle())
+            .into_iter()
+            .enumerate()
+            .filter_map(|(position, (node, shared))| {
+                shared.map(|strong_count| SharedNode {
+                    position,
+                    node,
+                    strong_count,
+                })
+            })
+            .collect()
+    }
+
+    // Общие узлы двух списков всегда образуют суффикс, поэтому более длинный список сначала
+    // пропускает разницу длин, а дальше оба идут в ногу: O(m + n) времени и O(1) памяти.
+    // Возвращает первый общий узел и его позицию в a
+    fn intersection_with_position(a: &Self, b: &Self) -> Option<(usize, Rc<NodeRc<T>>)> {
+        a.check_invariants();
+        b.check_invariants();
+
+        let skip_a = a.len.saturating_sub(b.len);
+        let skip_b = b.len.saturating_sub(a.len);
+        a.iter()
+            .skip(skip_a)
+            .zip(b.iter().skip(skip_b))
+            .enumerate()
+            .find(|(_, (a_node, b_node))| Rc::ptr_eq(a_node, b_node))
+            .map(|(offset, (node, _))| (skip_a + offset, node))
+    }
+
+    fn intersection(a: &Self, b: &Self) -> Option<Rc<NodeRc<T>>> {
+        Self::intersection_with_position(a, b).map(|(_, node)| node)
+    }
+
+    fn shared_suffix_len(a: &Self, b: &Self) -> usize {
+        Self::intersection_with_position(a, b).map_or(0, |(position, _)| a.len - position)
+    }
+
+    // Медленный итератор идёт на шаг, быстрый на два, как в has_cycle; возвращает узел перед
+    // серединой и середину. При чётной длине серединой считается первый узел второй половины
+    fn middle_with_prev(&self) -> (Link<T>, Link<T>) {
+        self.check_invariants();
+
+        let mut slow_iter = self.iter();
+        let mut fast_iter = self.iter();
+
+        let mut prev = None;
+        let mut middle = slow_iter.next();
+        while fast_iter.next().is_some() && fast_iter.next().is_some() {
+            prev = middle;
+            middle = slow_iter.next();
+        }
+        (prev, middle)
+    }
+
+    fn middle(&self) -> Option<Rc<NodeRc<T>>> {
+        self.middle_with_prev().1
+    }
+
+    // nth_from_end(0) — последний узел; ведущий итератор уходит на k + 1 узлов вперёд
+    fn nth_from_end(&self, k: usize) -> Option<Rc<NodeRc<T>>> {
+        self.check_invariants();
+
+        let mut lead = self.iter();
+        lead.nth(k)?;
+        let mut trail = self.iter();
+        let mut current = trail.next();
+        for _ in lead {
+            current = trail.next();
+        }
+        current
+    }
+
+    // Вторая половина временно разворачивается через RefCell и сравнивается с первой, затем
+    // восстанавливается
+    fn is_palindrome(&self) -> bool
+    where
+        T: PartialEq,
+    {
+        let (_, middle) = self.middle_with_prev();
+        let reversed = Self::reverse_chain(middle);
+
+        let front = self.iter().take(self.len / 2);
+        let back = NodeIter {
+            next: reversed.clone(),
+        };
+        let result = front.zip(back).all(|(f, b)| f.data == b.data);
+
+        // Узел перед серединой по-прежнему указывает на середину, она снова станет началом цепочки
+        Self::reverse_chain(reversed);
+        result
+    }
+
+    // Вторая половина начинается с middle(), при нечётной длине она длиннее на один элемент.
+    // Паникует там, где try_split_halves вернёт ошибку
+    fn split_halves(self) -> (Self, Self) {
+        match self.try_split_halves() {
+            Ok(halves) => halves,
+            Err(err) => panic!("Cannot split halves: {err}"),
+        }
+    }
+
+    fn try_split_halves(mut self) -> Result<(Self, Self), ListError> {
+        self.try_check_invariants()?;
+        self.unshare_prefix(self.len / 2)?;
+
+        let (prev, middle) = self.middle_with_prev();
+        let Some(prev) = prev else {
+            return Ok((self.empty_like(), self));
+        };
+        prev.set_next(None);
+
+        let second = ListNodeRc {
+            head: middle,
+            len: self.len - self.len / 2,
+            sharing: self.sharing,
+            invariants: self.invariants.clone(),
+            id: Cell::new(self.id.get().and(next_list_id())),
+        };
+        self.len /= 2;
+        self.renew_id();
+        Ok((self, second))
+    }
+
+    fn reverse_chain(mut link: Link<T>) -> Link<T> {
+        let mut reversed = None;
+        while let Some(node) = link {
+            link = node.next.replace(reversed);
+            reversed = Some(node);
+        }
+        reversed
+    }
+
+    // В строгом режиме Shared, если голову делит другой владелец; список тогда не меняется
+    fn try_pop_head(&mut self) -> Result<Option<T>, ListError> {
+        self.try_check_invariants()?;
+        self.unshare_prefix(1)?;
+
+        let Some(old_head) = self.head.take() else {
+            return Ok(None);
+        };
+        self.head = old_head.next_node();
+        self.len -= 1;
+
+        match Rc::try_unwrap(old_head) {
+            Ok(node) => Ok(Some(node.data)),
+            Err(_) => unreachable!("head is unique after unshare_prefix"),
+        }
+    }
+
+    // Последний узел self начинает указывать на голову other, поэтому собственным должен быть
+    // весь self; other может оставаться общим
+    fn try_join(mut self, mut other: Self) -> Result<Self, ListError> {
+        self.try_check_invariants()?;
+        other.try_check_invariants()?;
+
+        if other.head.is_none() {
+            return Ok(self);
+        }
+        if self.head.is_none() {
+            return Ok(other);
+        }
+
+        self.unshare_prefix(self.len)?;
+        if let Some(last_node) = self.iter().last() {
+            last_node.set_next(other.head.take());
+        }
+        self.len += std::mem::take(&mut other.len);
+        if other.id.get().is_none() {
+            self.forget_handles();
+        }
+        Ok(self)
+    }
+
+    // Ошибка возвращается до того, как из iter взят первый элемент
+    fn try_extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<(), ListError> {
+        self.try_check_invariants()?;
+        self.unshare_prefix(self.len)?;
+
+        let mut last = self.iter().last();
+        for data in iter {
+            let node = Rc::new(NodeRc {
+                data,
+                next: RefCell::new(None),
+            });
+            match &last {
+                Some(last_node) => last_node.set_next(Some(Rc::clone(&node))),
+                None => self.head = Some(Rc::clone(&node)),
+            }
+            self.len += 1;
+            last = Some(node);
+        }
+        Ok(())
+    }
+
+    // В отличие от iter, не ограничен длиной и по закольцованному списку идёт бесконечно; нужен
+    // поиску цикла и тестам
     fn iter_nodes(&self) -> NodeIter<T> {
         NodeIter {
             next: self.head.as_ref().map(Rc::clone),
@@ -264,6 +993,155 @@ impl<T> ListNodeRc<T> {
     }
 }
 
+impl<T: Clone> LinkedList<T> for ListNodeRc<T> {
+    fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn peek_head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.data)
+    }
+
+    // Паникует, если try_pop_head вернёт ошибку: в строгом режиме — когда голову делит другой
+    // владелец
+    fn pop_head(&mut self) -> Option<T> {
+        self.check_invariants();
+
+        match self.try_pop_head() {
+            Ok(data) => data,
+            Err(err) => panic!("Cannot pop head: {err}"),
+        }
+    }
+
+    fn push_head(&mut self, data: T) {
+        self.check_invariants();
+
+        let new_head = Rc::new(NodeRc {
+            data,
+            next: RefCell::new(self.head.take()),
+        });
+
+        self.head = Some(new_head);
+        self.len += 1;
+
+        self.check_invariants();
+    }
+
+    // Паникует там, где try_join вернёт ошибку
+    fn join(self, other: Self) -> Self {
+        self.check_invariants();
+        other.check_invariants();
+
+        match self.try_join(other) {
+            Ok(joined) => joined,
+            Err(err) => panic!("Cannot join: {err}"),
+        }
+    }
+
+    fn try_divide_at(mut self, position: usize) -> Result<(Self, Self), ListError> {
+        self.try_check_invariants()?;
+
+        if position == 0 {
+            return Ok((self.empty_like(), self));
+        }
+        if position > self.len {
+            return Err(self.out_of_bounds(position));
+        }
+        self.unshare_prefix(position)?;
+
+        let prev_node = self
+            .get_node_at(position - 1)
+            .ok_or_else(|| self.out_of_bounds(position))?;
+
+        let head2 = prev_node.next_node();
+        prev_node.set_next(None);
+
+        let list2 = ListNodeRc {
+            head: head2,
+            len: self.len - position,
+            sharing: self.sharing,
+            invariants: self.invariants.clone(),
+            id: Cell::new(self.id.get().and(next_list_id())),
+        };
+        self.len = position;
+        self.renew_id();
+
+        self.check_invariants();
+        Ok((self, list2))
+    }
+
+    fn try_append_at(&mut self, position: usize, data: T) -> Result<(), ListError> {
+        self.try_check_invariants()?;
+
+        if position == 0 {
+            self.push_head(data);
+            return Ok(());
+        }
+        if position > self.len {
+            return Err(self.out_of_bounds(position));
+        }
+        self.unshare_prefix(position)?;
+
+        let prev_node = self
+            .get_node_at(position - 1)
+            .ok_or_else(|| self.out_of_bounds(position))?;
+
+        let new_node = Rc::new(NodeRc {
+            data,
+            next: RefCell::new(prev_node.next_node()),
+        });
+
+        *prev_node.next.borrow_mut() = Some(new_node);
+        self.len += 1;
+
+        self.check_invariants();
+        Ok(())
+    }
+
+    fn try_remove_at(&mut self, position: usize) -> Result<T, ListError> {
+        self.try_check_invariants()?;
+
+        if self.is_empty() {
+            return Err(ListError::Empty);
+        }
+        if position >= self.len {
+            return Err(self.out_of_bounds(position));
+        }
+        // Меняются предыдущий узел и сам удаляемый
+        self.unshare_prefix(position + 1)?;
+
+        if position == 0 {
+            return self.try_pop_head()?.ok_or(ListError::Empty);
+        }
+
+        let (prev_node, node_to_remove) = self
+            .get_node_at(position - 1)
+            .and_then(|prev_node| {
+                let node = prev_node.next_node()?;
+                Some((prev_node, node))
+            })
+            .ok_or_else(|| self.out_of_bounds(position))?;
+
+        // Одна ссылка у предыдущего узла и одна локальная
+        if Rc::strong_count(&node_to_remove) > 2 {
+            return Err(ListError::Shared);
+        }
+
+        prev_node.set_next(node_to_remove.next_node());
+        self.len -= 1;
+
+        self.check_invariants();
+
+        Rc::try_unwrap(node_to_remove)
+            .map(|node| node.data)
+            .map_err(|_| ListError::Shared)
+    }
+}
+
 struct NodeIter<T> {
     next: Link<T>,
 }
@@ -272,19 +1150,26 @@ impl<T> Iterator for NodeIter<T> {
     type Item = Rc<NodeRc<T>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.next.take().map(|node| {
+        self.next.take().inspect(|node| {
             let next = {
                 let node_ref = node.next.borrow();
                 node_ref.as_ref().map(Rc::clone)
             };
             self.next = next;
-            node
         })
     }
 }
 
 impl<T> Drop for ListNodeRc<T> {
     fn drop(&mut self) {
+        // Узлы цикла держат друг друга, поэтому обратная ссылка обрезается до освобождения. Если
+        // хоть один узел делят другие версии или handle, цикл принадлежит и им и остаётся целым
+        if let Some(info) = self.find_cycle() {
+            if self.nodes_with_owners(Some(info)).all(|(_, owners)| owners.is_none()) {
+                self.break_cycle();
+            }
+        }
+
         let mut current = self.head.take();
         while let Some(node) = current {
             if let Ok(node_inner) = Rc::try_unwrap(node) {
@@ -296,6 +1181,102 @@ impl<T> Drop for ListNodeRc<T> {
     }
 }
 
+// Общие узлы копируются, только если список копирует при записи. Паникует там, где
+// try_into_iter вернёт ошибку; для строгого списка с T: Clone есть into_iter_cloned
+impl<T> IntoIterator for ListNodeRc<T> {
+    type Item = T;
+    type IntoIter = IntoIterRc<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.check_invariants();
+
+        match self.try_into_iter() {
+            Ok(iter) => iter,
+            Err(err) => panic!("Cannot take nodes: {err}"),
+        }
+    }
+}
+
+// Данные живут внутри узлов с общим владением, поэтому итерация по ссылке отдаёт сами узлы
+impl<T> IntoIterator for &ListNodeRc<T> {
+    type Item = Rc<NodeRc<T>>;
+    type IntoIter = IterRc<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Паникует, если iter_mut вернёт ошибку: в строгом режиме — когда узел удерживается снаружи
+impl<'a, T> IntoIterator for &'a mut ListNodeRc<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMutRc<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self.iter_mut() {
+            Ok(iter) => iter,
+            Err(err) => panic!("Cannot iterate mutably: {err}"),
+        }
+    }
+}
+
+impl<T: Clone> FromIterator<T> for ListNodeRc<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = ListNodeRc::new();
+        list.extend(iter);
+        list
+    }
+}
+
+// Копия за O(1): новая версия делит все узлы с исходной
+impl<T> Clone for ListNodeRc<T> {
+    fn clone(&self) -> Self {
+        self.forget_handles();
+        Self {
+            head: self.head.clone(),
+            len: self.len,
+            sharing: self.sharing,
+            invariants: self.invariants.clone(),
+            id: Cell::new(None),
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for ListNodeRc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for ListNodeRc<T> {}
+
+impl<T: PartialOrd> PartialOrd for ListNodeRc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord> Ord for ListNodeRc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash> Hash for ListNodeRc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for node in self.iter() {
+            node.hash(state);
+        }
+        state.write_usize(self.len);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ListNodeRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 fn main() {
     let mut list = ListNodeRc::new();
     list.push_head(1);
@@ -316,14 +1297,23 @@ fn create_and_drop_large_list() {
     list.extend(0..10_000);
 }
 
+// Элементы добавляются в конец списка, как у коллекций std. Паникует там, где try_extend
+// вернёт ошибку
 impl<T> Extend<T> for ListNodeRc<T> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-        for item in iter {
-            self.push_head(item);
+        self.check_invariants();
+        if let Err(err) = self.try_extend(iter) {
+            panic!("Cannot extend: {err}");
         }
     }
 }
 
+impl<'a, T: Copy + 'a> Extend<&'a T> for ListNodeRc<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
 #[test]
 fn test_large_list_drop() {
     create_and_drop_large_list();
@@ -398,6 +1388,628 @@ fn test_make_cycle_at() {
     assert!(list.has_cycle());
 }
 
+#[test]
+fn test_find_and_break_cycle() {
+    for len in 1..8 {
+        for position in 0..len {
+            let mut list: ListNodeRc<usize> = (0..len).collect();
+            assert_eq!(list.find_cycle(), None);
+
+            list.make_cycle_at(position + 1);
+            let info = CycleInfo {
+                entry_position: position,
+                cycle_len: len - position,
+                tail_position: len - 1,
+            };
+            assert_eq!(list.find_cycle(), Some(info));
+
+            assert_eq!(list.break_cycle(), Some(info));
+            assert_eq!(list.break_cycle(), None);
+            let values: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+            assert_eq!(values, (0..len).collect::<Vec<_>>());
+        }
+    }
+
+    let mut empty: ListNodeRc<i32> = ListNodeRc::new();
+    assert_eq!(empty.find_cycle(), None);
+    assert_eq!(empty.break_cycle(), None);
+}
+
+#[test]
+#[should_panic(expected = "entry_position: 1, cycle_len: 3, tail_position: 3")]
+fn test_check_invariants_reports_cycle() {
+    let mut list = (0..4)
+        .collect::<ListNodeRc<i32>>()
+        .with_invariant_policy(InvariantPolicy::Always);
+    list.make_cycle_at(2);
+    list.push_head(5);
+}
+
+// Считает живые экземпляры, чтобы проверить, что узлы действительно освобождаются
+struct DropCounter(Rc<Cell<usize>>);
+
+impl DropCounter {
+    fn new(live: &Rc<Cell<usize>>) -> Self {
+        live.set(live.get() + 1);
+        DropCounter(Rc::clone(live))
+    }
+}
+
+// Копия — ещё один живой экземпляр
+impl Clone for DropCounter {
+    fn clone(&self) -> Self {
+        DropCounter::new(&self.0)
+    }
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+#[test]
+fn test_drop_frees_all_nodes() {
+    let live = Rc::new(Cell::new(0));
+    let make_list = |len| (0..len).map(|_| DropCounter::new(&live)).collect::<ListNodeRc<_>>();
+
+    drop(make_list(5));
+    assert_eq!(live.get(), 0);
+
+    for position in 0..5 {
+        let mut list = make_list(5);
+        list.make_cycle_at(position + 1);
+        drop(list);
+        assert_eq!(live.get(), 0, "cycle at {position}");
+    }
+
+    // Узел, на который есть внешняя ссылка, живёт вместе с хвостом, пока ссылка не отпущена
+    let list = make_list(5);
+    let handle = list.get_node_at(2).expect("node exists");
+    drop(list);
+    assert_eq!(live.get(), 3);
+    drop(handle);
+    assert_eq!(live.get(), 0);
+
+    // Цикл, который делит копия, остаётся ей целым и освобождается вместе с последней копией
+    let mut list = make_list(5);
+    list.make_cycle_at(2);
+    let copy = list.clone();
+    drop(list);
+    assert_eq!(live.get(), 5);
+    assert!(copy.has_cycle());
+    drop(copy);
+    assert_eq!(live.get(), 0);
+
+    // Handle на хвосте цикла держит весь цикл; начало списка до цикла освобождается
+    let mut list = make_list(5);
+    list.make_cycle_at(2);
+    let tail = list.get_node_at(4).expect("node exists");
+    drop(list);
+    assert_eq!(live.get(), 4);
+    let entry = tail.next_node().expect("cycle is kept");
+    assert_eq!(Rc::strong_count(&entry), 2);
+    drop(entry);
+    tail.set_next(None);
+    assert_eq!(live.get(), 1);
+    drop(tail);
+    assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn test_mutable_iteration() {
+    let mut list = (1..=4)
+        .collect::<ListNodeRc<i32>>()
+        .strict()
+        .with_invariant_policy(InvariantPolicy::Always);
+    for data in list.iter_mut().expect("nodes are not shared") {
+        *data *= 10;
+    }
+    assert_eq!(list.for_each_mut(|data| *data += 1), Ok(()));
+    assert_eq!(list.update_at(3, |data| *data = 0), Ok(()));
+    let values: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(values, vec![11, 21, 31, 0]);
+
+    // Узел удерживается снаружи: обход отказывает целиком, узлы до него можно менять, а узлы
+    // после него видны через handle
+    let handle = list.get_node_at(1).expect("node exists");
+    assert_eq!(list.iter_mut().err(), Some(ListError::Shared));
+    assert_eq!(list.for_each_mut(|data| *data = -1), Err(ListError::Shared));
+    assert_eq!(list.update_at(1, |data| *data = -1), Err(ListError::Shared));
+    assert_eq!(list.update_at(2, |data| *data = -1), Err(ListError::Shared));
+    assert_eq!(list.update_at(0, |data| *data = 10), Ok(()));
+    drop(handle);
+    assert_eq!(list.update_at(2, |data| *data = 30), Ok(()));
+    let values: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(values, vec![10, 21, 30, 0]);
+
+    assert_eq!(
+        list.update_at(4, |_| {}),
+        Err(ListError::OutOfBounds { position: 4, len: 4 })
+    );
+    let mut empty: ListNodeRc<i32> = ListNodeRc::new();
+    assert_eq!(empty.update_at(0, |_| {}), Err(ListError::Empty));
+    assert_eq!(empty.for_each_mut(|_| {}), Ok(()));
+
+    list.make_cycle_at(1);
+    assert_eq!(list.for_each_mut(|_| {}), Err(ListError::CycleDetected));
+}
+
+#[test]
+fn test_persistent_versions() {
+    let empty: ListNodeRc<i32> = ListNodeRc::new();
+    let v1 = empty.cons(1);
+    let v2 = v1.cons(2);
+    let v3a = v2.cons(3);
+    let v3b = v2.cons(30);
+
+    let values = |list: &ListNodeRc<i32>| list.iter().map(|node| node.data).collect::<Vec<_>>();
+    assert_eq!(values(&v3a), vec![3, 2, 1]);
+    assert_eq!(values(&v3b), vec![30, 2, 1]);
+    assert_eq!(values(&v2), vec![2, 1]);
+    assert_eq!((v3a.len(), v3b.len()), (3, 3));
+
+    // Обе ветки делят узлы v2
+    let v2_head = v2.head.as_ref().expect("v2 is not empty");
+    assert!(Rc::ptr_eq(v3a.tail().head.as_ref().expect("tail is not empty"), v2_head));
+    assert!(Rc::ptr_eq(v3b.tail().head.as_ref().expect("tail is not empty"), v2_head));
+    assert_eq!(values(&v3a.tail().tail()), vec![1]);
+    assert!(empty.tail().is_empty());
+    assert!(v1.tail().tail().is_empty());
+
+    let copy = v3a.clone();
+    let copy_head = copy.head.as_ref().expect("copy is not empty");
+    assert!(Rc::ptr_eq(copy_head, v3a.head.as_ref().expect("v3a is not empty")));
+    assert_eq!(copy, v3a);
+
+    // into_iter копирует данные из общих узлов и не трогает другие версии
+    assert_eq!(copy.into_iter().collect::<Vec<_>>(), vec![3, 2, 1]);
+    assert_eq!(values(&v3a), vec![3, 2, 1]);
+    assert_eq!(values(&v3b), vec![30, 2, 1]);
+}
+
+#[test]
+fn test_persistent_versions_are_freed() {
+    let live = Rc::new(Cell::new(0));
+    let mut history = vec![ListNodeRc::new()];
+    for i in 0..100 {
+        let base = &history[i / 2];
+        let next = base.cons(DropCounter::new(&live));
+        history.push(next);
+    }
+    assert_eq!(live.get(), 100);
+    let last = history.last().expect("history is not empty").clone();
+    history.truncate(1);
+    assert!(live.get() > 0);
+    drop(last);
+    assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn test_copy_on_write() {
+    // Режим по умолчанию
+    let base: ListNodeRc<i32> = (1..=5).collect();
+    let values = |list: &ListNodeRc<i32>| list.iter().map(|node| node.data).collect::<Vec<_>>();
+
+    // Копируется только начало до удаляемого узла, хвост остаётся общим
+    let mut version = base.clone();
+    assert_eq!(version.try_remove_at(1), Ok(2));
+    assert_eq!(values(&version), vec![1, 3, 4, 5]);
+    let node = |list: &ListNodeRc<i32>, position| list.get_node_at(position).expect("node exists");
+    assert!(!Rc::ptr_eq(&node(&version, 0), &node(&base, 0)));
+    assert!(Rc::ptr_eq(&node(&version, 1), &node(&base, 2)));
+
+    version.push_head(0);
+    assert_eq!(version.pop_head(), Some(0));
+    assert_eq!(version.pop_head(), Some(1));
+    version.append_at(1, 35);
+    assert_eq!(version.update_at(2, |data| *data *= 10), Ok(()));
+    let version = version.join((6..=7).collect());
+    assert_eq!(values(&version), vec![3, 35, 40, 5, 6, 7]);
+
+    let mut other = base.clone();
+    assert_eq!(other.for_each_mut(|data| *data = -*data), Ok(()));
+    other.extend([-6]);
+    let (left, right) = other.divide_at(2).expect("position is in bounds");
+    assert_eq!((values(&left), values(&right)), (vec![-1, -2], vec![-3, -4, -5, -6]));
+
+    // Копии и продолжения наследуют режим
+    let mut tail = base.tail().cons(10);
+    assert_eq!(tail.try_remove_at(3), Ok(4));
+    assert_eq!(values(&tail), vec![10, 2, 3, 5]);
+
+    assert_eq!(values(&base), vec![1, 2, 3, 4, 5]);
+    assert_eq!(base.len(), 5);
+}
+
+#[test]
+fn test_strict_sharing() {
+    let base = (1..=4).collect::<ListNodeRc<i32>>().strict();
+    let mut version = base.clone();
+    assert_eq!(version.try_remove_at(2), Err(ListError::Shared));
+    assert_eq!(version.try_append_at(1, 9), Err(ListError::Shared));
+    assert_eq!(version.update_at(3, |data| *data = 0), Err(ListError::Shared));
+    assert_eq!(version.for_each_mut(|data| *data = 0), Err(ListError::Shared));
+    assert_eq!(version.try_make_cycle_at(0), Err(ListError::Shared));
+    assert_eq!(base.clone().try_divide_at(2), Err(ListError::Shared));
+    assert_eq!(
+        base.clone().try_divide_at(5),
+        Err(ListError::OutOfBounds { position: 5, len: 4 })
+    );
+
+    // Новая голова не меняет общие узлы
+    version.push_head(0);
+    assert_eq!(version.pop_head(), Some(0));
+    assert_eq!(version, base);
+
+    // try_* возвращают Shared и ничего не меняют
+    assert_eq!(version.clone().try_pop_head(), Err(ListError::Shared));
+    assert_eq!(version.clone().try_extend([5]), Err(ListError::Shared));
+    assert_eq!(version.clone().try_join(base.clone()).err(), Some(ListError::Shared));
+    assert_eq!(version.clone().try_split_halves().err(), Some(ListError::Shared));
+    assert_eq!(base.clone().try_pop_head(), Err(ListError::Shared));
+    assert_eq!(base.clone().try_into_iter().err(), Some(ListError::Shared));
+    let values: Vec<_> = base.clone().into_iter_cloned().collect();
+    assert_eq!(values, vec![1, 2, 3, 4]);
+    // Общим может оставаться присоединяемый список
+    let joined = ListNodeRc::from_iter([0])
+        .strict()
+        .try_join(base.clone())
+        .expect("self is unique");
+    assert_eq!(joined.len(), 5);
+
+    // Режим переключается в обе стороны
+    let mut version = version.copy_on_write();
+    assert_eq!(version.try_remove_at(2), Ok(3));
+    let mut version = version.strict();
+    let _copy = version.clone();
+    assert_eq!(version.try_remove_at(0), Err(ListError::Shared));
+
+    let values: Vec<_> = base.iter().map(|node| node.data).collect();
+    assert_eq!(values, vec![1, 2, 3, 4]);
+}
+
+#[test]
+#[should_panic(expected = "Cannot pop head: node is shared")]
+fn test_strict_pop_head_panics() {
+    let base = (1..=3).collect::<ListNodeRc<i32>>().strict();
+    let mut version = base.clone();
+    version.pop_head();
+}
+
+#[test]
+fn test_invariant_policy() {
+    let with_policy =
+        |policy| (0..4).collect::<ListNodeRc<i32>>().strict().with_invariant_policy(policy);
+
+    // Цикл не проверяется, операции у головы не обходят список
+    let mut list = with_policy(InvariantPolicy::Never);
+    list.make_cycle_at(2);
+    list.push_head(5);
+    assert_eq!(list.try_remove_at(0), Ok(5));
+    assert_eq!(list.find_cycle().map(|info| info.entry_position), Some(1));
+
+    // Обходы до конца списка ограничены длиной, поэтому без проверок они не зацикливаются. Вход
+    // в цикл держат два узла, и строгий список отказывается менять последний узел
+    let mut list = with_policy(InvariantPolicy::Never);
+    list.make_cycle_at(3);
+    assert_eq!(list.middle().map(|node| node.data), Some(2));
+    assert_eq!(list.nth_from_end(0).map(|node| node.data), Some(3));
+    assert_eq!(list.try_extend([4, 5]), Err(ListError::Shared));
+    let other = with_policy(InvariantPolicy::Never);
+    assert_eq!(list.try_join(other).err(), Some(ListError::Shared));
+
+    // Первая проверка в make_cycle_at, затем две в push_head пропускаются, четвёртая находит цикл
+    let mut list = with_policy(InvariantPolicy::EveryN(3));
+    list.make_cycle_at(2);
+    list.push_head(5);
+    assert_eq!(list.try_append_at(1, 6), Err(ListError::CycleDetected));
+
+    let mut list = with_policy(InvariantPolicy::Always);
+    list.make_cycle_at(2);
+    assert_eq!(list.try_append_at(1, 6), Err(ListError::CycleDetected));
+
+    // Копии и части списка сохраняют политику
+    let list = with_policy(InvariantPolicy::Never);
+    assert_eq!(list.clone().invariants.policy(), InvariantPolicy::Never);
+    let (left, right) = list.split_halves();
+    assert_eq!(left.invariants.policy(), InvariantPolicy::Never);
+    assert_eq!(right.invariants.policy(), InvariantPolicy::Never);
+}
+
+#[test]
+fn test_structure_dumps() {
+    let mut list: ListNodeRc<&str> = ["a", "b\"q", "c"].into_iter().collect();
+    let handle = list.get_node_at(1).expect("node exists");
+    let address = |position| {
+        let node = list.get_node_at(position).expect("node exists");
+        format!("{:p}", Rc::as_ptr(&node))
+    };
+
+    let expected = format!(
+        "head -> #0\n#0 {} \"a\" -> #1\n#1 {} \"b\\\"q\" (rc=2) -> #2\n#2 {} \"c\" -> None\n",
+        address(0),
+        address(1),
+        address(2)
+    );
+    assert_eq!(list.to_ascii_diagram(), expected);
+
+    let dot = list.to_dot();
+    assert!(dot.starts_with("digraph ListNodeRc {\n"));
+    assert!(dot.contains("    head -> n0;\n    n1 [label=\"#1 "));
+    assert!(dot.contains("\\n\\\"b\\\\\\\"q\\\"\", style=filled"));
+    assert!(dot.contains("xlabel=\"rc=2\""));
+    assert!(dot.contains("    n1 -> n2;\n}\n"));
+    drop(handle);
+
+    // Вход в цикл держат два узла списка, это не считается общим владением
+    list.make_cycle_at(2);
+    let ascii = list.to_ascii_diagram();
+    assert!(!ascii.contains("rc="));
+    assert!(ascii.ends_with("\"c\" -> #1 (cycle)\n"));
+    assert!(list
+        .to_dot()
+        .contains("    n2 -> n1 [style=dashed, color=red, label=\"cycle\"];\n"));
+
+    let empty: ListNodeRc<i32> = ListNodeRc::new();
+    assert_eq!(empty.to_ascii_diagram(), "head -> None\n");
+    assert!(!empty.to_dot().contains("->"));
+}
+
+#[test]
+fn test_intersection() {
+    let base: ListNodeRc<i32> = (1..=5).collect();
+    let branch = base.tail().tail().cons(10).cons(20);
+    let values: Vec<_> = branch.iter().map(|node| node.data).collect();
+    assert_eq!(values, vec![20, 10, 3, 4, 5]);
+
+    for (a, b) in [(&base, &branch), (&branch, &base)] {
+        let node = ListNodeRc::intersection(a, b).expect("lists share a suffix");
+        assert!(Rc::ptr_eq(&node, &base.get_node_at(2).expect("node exists")));
+        assert_eq!(ListNodeRc::shared_suffix_len(a, b), 3);
+    }
+
+    // Разной длины, общий только последний узел
+    let short = base.tail().tail().tail().tail();
+    let long = short.cons(0).cons(0).cons(0).cons(0).cons(0);
+    assert_eq!(ListNodeRc::intersection(&long, &short).map(|node| node.data), Some(5));
+    assert_eq!(ListNodeRc::shared_suffix_len(&long, &short), 1);
+
+    // Равные значения без общих узлов не пересекаются
+    let copy: ListNodeRc<i32> = (1..=5).collect();
+    assert!(ListNodeRc::intersection(&base, &copy).is_none());
+    assert_eq!(ListNodeRc::shared_suffix_len(&base, &copy), 0);
+
+    let same = base.clone();
+    assert_eq!(ListNodeRc::shared_suffix_len(&base, &same), 5);
+    let empty = ListNodeRc::new();
+    assert!(ListNodeRc::intersection(&base, &empty).is_none());
+    assert_eq!(ListNodeRc::shared_suffix_len(&empty, &empty), 0);
+}
+
+#[test]
+fn test_sharing_report() {
+    let mut list: ListNodeRc<i32> = (1..=5).collect();
+    assert!(list.sharing_report().is_empty());
+
+    let handle = list.handle_at(1).expect("node exists");
+    let version = list.tail().tail().tail();
+    let report = list.sharing_report();
+    let rows: Vec<_> = report
+        .iter()
+        .map(|row| (row.position, row.node.data, row.strong_count))
+        .collect();
+    assert_eq!(rows, vec![(1, 2, 2), (3, 4, 2)]);
+    assert!(Rc::ptr_eq(&report[0].node, &handle.0));
+    drop(report);
+
+    // Вход в цикл держит последний узел, это не общий узел
+    drop((handle, version));
+    list.make_cycle_at(3);
+    assert!(list.sharing_report().is_empty());
+}
+
+#[test]
+fn test_node_handles() {
+    let mut list = ["parse", "emit"].into_iter().collect::<ListNodeRc<_>>().strict();
+    let parse = list.handle_at(0).expect("node exists");
+
+    let check = list.insert_after(&parse, "check").expect("handle is valid");
+    // Правка идёт через узел handle, поэтому handle на узлы перед ним ей не мешают
+    let lower = list.insert_after(&check, "lower").expect("handle is valid");
+    drop(parse);
+    assert_eq!(list.value(&lower), &"lower");
+    assert_eq!(list.next(&lower).map(|next| *list.value(&next)), Some("emit"));
+    assert_eq!(list.len(), 4);
+    let values: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(values, vec!["parse", "check", "lower", "emit"]);
+
+    // На lower есть handle, поэтому удалить его нельзя
+    assert_eq!(list.remove_after(&check), Err(ListError::Shared));
+    drop(lower);
+    assert_eq!(list.remove_after(&check), Ok(Some("lower")));
+    assert_eq!(list.remove_after(&check), Ok(Some("emit")));
+    assert_eq!(list.remove_after(&check), Ok(None));
+    assert_eq!(list.len(), 2);
+
+    list.push_head("read");
+    list.insert_after(&check, "emit").expect("handle is valid");
+    let values: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(values, vec!["read", "parse", "check", "emit"]);
+
+    let other: ListNodeRc<&str> = ["check"].into_iter().collect();
+    let foreign = other.handle_at(0).expect("node exists");
+    assert_eq!(list.insert_after(&foreign, "x").err(), Some(ListError::StaleHandle));
+    assert_eq!(list.remove_after(&foreign), Err(ListError::StaleHandle));
+    assert_eq!(list.len(), 4);
+
+    // После деления handle ищутся от головы: узел правится в той части, где он лежит
+    let emit = list.handle_at(3).expect("node exists");
+    let (mut left, mut right) = list.divide_at(2).expect("position is in bounds");
+    assert_eq!(left.insert_after(&emit, "x").err(), Some(ListError::StaleHandle));
+    right.insert_after(&emit, "write").expect("handle is valid");
+    assert_eq!(right.remove_after(&check), Err(ListError::Shared));
+    drop(emit);
+    assert_eq!(right.remove_after(&check), Ok(Some("emit")));
+    let values: Vec<_> = right.iter_nodes().map(|node| node.data).collect();
+    assert_eq!(values, vec!["check", "write"]);
+    assert_eq!(left.len(), 2);
+}
+
+#[test]
+fn test_node_handles_with_versions() {
+    let values = |list: &ListNodeRc<i32>| list.iter().map(|node| node.data).collect::<Vec<_>>();
+
+    // Строгий режим: узел виден из копии, правка через handle отказывает
+    let mut list = (1..=4).collect::<ListNodeRc<i32>>().strict();
+    let copy = list.clone();
+    let handle = list.handle_at(1).expect("node exists");
+    assert_eq!(list.insert_after(&handle, 9).err(), Some(ListError::Shared));
+    assert_eq!(list.remove_after(&handle), Err(ListError::Shared));
+    assert_eq!((values(&list), values(&copy)), (vec![1, 2, 3, 4], vec![1, 2, 3, 4]));
+
+    // Копирование при записи: копия и её длина не меняются
+    let mut list = list.copy_on_write();
+    let inserted = list.insert_after(&handle, 9).expect("handle is valid");
+    assert_eq!(values(&list), vec![1, 2, 9, 3, 4]);
+    assert_eq!((copy.len(), values(&copy)), (4, vec![1, 2, 3, 4]));
+    assert_eq!(list.value(&inserted), &9);
+
+    // handle остался у старого узла, который теперь принадлежит только копии
+    assert_eq!(list.remove_after(&handle), Err(ListError::StaleHandle));
+    let handle = list.handle_at(2).expect("node exists");
+    assert_eq!(list.remove_after(&handle), Ok(Some(3)));
+    assert_eq!((list.len(), values(&list)), (4, vec![1, 2, 9, 4]));
+    assert_eq!((copy.len(), values(&copy)), (4, vec![1, 2, 3, 4]));
+
+    // Удалённый узел остаётся у handle, и тот больше не правит список
+    let mut list = (1..=3).collect::<ListNodeRc<i32>>().copy_on_write();
+    let first = list.handle_at(0).expect("node exists");
+    let second = list.handle_at(1).expect("node exists");
+    assert_eq!(list.remove_after(&first), Ok(Some(2)));
+    assert_eq!(list.insert_after(&second, 5).err(), Some(ListError::StaleHandle));
+    list.insert_after(&first, 5).expect("handle is valid");
+    assert_eq!((list.len(), values(&list)), (3, vec![1, 5, 3]));
+}
+
+#[test]
+fn test_try_errors_on_cycle_and_shared_nodes() {
+    let mut list = ListNodeRc::new()
+        .strict()
+        .with_invariant_policy(InvariantPolicy::Always);
+    list.push_head(1);
+    list.push_head(2);
+    list.push_head(3);
+
+    let handle = list.get_node_at(1).expect("node exists");
+    assert_eq!(list.try_remove_at(1), Err(ListError::Shared));
+    drop(handle);
+    assert_eq!(list.try_remove_at(1), Ok(2));
+
+    let handle = list.get_node_at(0).expect("node exists");
+    assert_eq!(list.try_remove_at(0), Err(ListError::Shared));
+    drop(handle);
+
+    assert_eq!(
+        list.try_make_cycle_at(2),
+        Err(ListError::OutOfBounds { position: 2, len: 2 })
+    );
+    assert_eq!(list.try_make_cycle_at(0), Ok(()));
+    assert_eq!(list.try_make_cycle_at(0), Err(ListError::CycleDetected));
+    assert_eq!(list.try_append_at(1, 5), Err(ListError::CycleDetected));
+    assert_eq!(list.try_remove_at(1), Err(ListError::CycleDetected));
+
+    let mut empty: ListNodeRc<i32> = ListNodeRc::new();
+    assert_eq!(empty.try_make_cycle_at(0), Err(ListError::Empty));
+}
+
+#[test]
+fn test_for_loops() {
+    let list: ListNodeRc<i32> = (1..=3).collect();
+
+    let mut seen = Vec::new();
+    for node in &list {
+        seen.push(node.data);
+    }
+    assert_eq!(seen, vec![1, 2, 3]);
+
+    let mut list = list;
+    for data in &mut list {
+        *data *= 10;
+    }
+    let owned: Vec<_> = list.into_iter().collect();
+    assert_eq!(owned, vec![10, 20, 30]);
+
+    // Данные без Clone живут в строгом списке и забираются из собственных узлов
+    struct Token(u8);
+    let mut tokens = ListNodeRc::new_strict();
+    tokens.try_extend((1..=3).map(Token)).expect("list is not shared");
+    assert_eq!(tokens.into_iter().map(|Token(id)| id).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+    // Копирование при записи копирует общие узлы, другая версия не меняется
+    let shared = ListNodeRc::from_iter([1, 2, 3]).copy_on_write();
+    let version = shared.tail();
+    assert_eq!(shared.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(version.iter().map(|node| node.data).collect::<Vec<_>>(), vec![2, 3]);
+
+    // Закольцованный список отдаёт ровно len элементов
+    let mut cyclic: ListNodeRc<i32> = ListNodeRc::from_iter(0..4)
+        .with_invariant_policy(InvariantPolicy::Never)
+        .copy_on_write();
+    cyclic.make_cycle_at(2);
+    assert_eq!(cyclic.into_iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_middle_and_nth_from_end() {
+    for len in 0..10 {
+        let list: ListNodeRc<i32> = (0..len).collect();
+        assert_eq!(list.middle().map(|node| node.data), (len > 0).then_some(len / 2));
+        for k in 0..12 {
+            let expected = (k < len).then(|| len - 1 - k);
+            assert_eq!(list.nth_from_end(k as usize).map(|node| node.data), expected);
+        }
+    }
+}
+
+#[test]
+fn test_is_palindrome() {
+    let cases: [&[i32]; 7] = [&[], &[1], &[1, 1], &[1, 2], &[1, 2, 1], &[1, 2, 2, 1], &[1, 2, 3, 1]];
+    for case in cases {
+        let list: ListNodeRc<i32> = case.iter().copied().collect();
+        let expected = case.iter().eq(case.iter().rev());
+        assert_eq!(list.is_palindrome(), expected, "{case:?}");
+        // Список восстановлен после временного разворота
+        let values: Vec<_> = list.iter_nodes().map(|node| node.data).collect();
+        assert_eq!(values, case);
+    }
+}
+
+#[test]
+fn test_split_halves() {
+    for len in 0..10 {
+        let list: ListNodeRc<i32> = (0..len).collect();
+        let (first, second) = list.split_halves();
+        let first_values: Vec<_> = first.iter_nodes().map(|node| node.data).collect();
+        let second_values: Vec<_> = second.iter_nodes().map(|node| node.data).collect();
+        assert_eq!(first_values, (0..len / 2).collect::<Vec<_>>());
+        assert_eq!(second_values, (len / 2..len).collect::<Vec<_>>());
+        assert_eq!((first.len(), second.len()), (len as usize / 2, (len - len / 2) as usize));
+    }
+}
+
+#[test]
+fn test_iter_stops_on_cycle() {
+    let mut list: ListNodeRc<i32> = (1..=4).collect();
+    list.make_cycle_at(2);
+    assert_eq!(list.len(), 4);
+    let values: Vec<_> = list.iter().map(|node| node.data).collect();
+    assert_eq!(values, vec![1, 2, 3, 4]);
+}
+
+#[cfg(test)]
+linked_list::conformance_tests!(ListNodeRc<i32>, make_cycle = ListNodeRc::make_cycle_at);
+
 // struct Ref<'a, T> {
 //     data: *mut T,
 //     _marker: PhantomData<&'a T>,
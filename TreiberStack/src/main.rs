@@ -0,0 +1,485 @@
+// Стек Трайбера без блокировок: узлы как в ListNodeBox (данные и ссылка на следующий), голова
+// атомарная, push и pop — циклы CAS. Снятые узлы освобождаются через hazard pointers: поток
+// публикует узел, который собирается читать, и такой узел не освобождается, а значит его адрес
+// не может достаться новому узлу, поэтому ABA не возникает
+#![allow(unused)]
+
+use std::{
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+};
+
+// Сколько снятых узлов копится до попытки их освободить
+const RECLAIM_THRESHOLD: usize = 64;
+
+// Данные забирает тот, чей CAS снял узел, поэтому сам узел их не удаляет. next атомарный:
+// по нему же снятый узел встаёт в список ожидающих освобождения, пока другой поток ещё может
+// читать его под hazard pointer
+struct NodeBox<T> {
+    data: ManuallyDrop<T>,
+    next: AtomicPtr<NodeBox<T>>,
+}
+
+// Ячейка hazard pointer. Ячейки не освобождаются до удаления стека, поток занимает свободную
+// на время одной операции. next не меняется после публикации ячейки
+struct HazardSlot {
+    protected: AtomicPtr<()>,
+    active: AtomicBool,
+    next: *mut HazardSlot,
+}
+
+struct HazardGuard<'a> {
+    slot: &'a HazardSlot,
+}
+
+impl HazardGuard<'_> {
+    fn protect<T>(&self, node: *mut NodeBox<T>) {
+        self.slot.protected.store(node.cast(), Ordering::SeqCst);
+    }
+
+    fn clear(&self) {
+        self.slot.protected.store(ptr::null_mut(), Ordering::Release);
+    }
+}
+
+impl Drop for HazardGuard<'_> {
+    fn drop(&mut self) {
+        self.clear();
+        self.slot.active.store(false, Ordering::Release);
+    }
+}
+
+struct TreiberStack<T> {
+    head: AtomicPtr<NodeBox<T>>,
+    hazards: AtomicPtr<HazardSlot>,
+    // Снятые узлы, связанные через next; освобождаются в reclaim
+    retired: AtomicPtr<NodeBox<T>>,
+    retired_count: AtomicUsize,
+    // Стек владеет T и передаёт их между потоками
+    _marker: PhantomData<*mut T>,
+}
+
+// SAFETY: значения T переходят между потоками только целиком, через push и pop, поэтому
+// достаточно T: Send, как у каналов std
+unsafe impl<T: Send> Send for TreiberStack<T> {}
+unsafe impl<T: Send> Sync for TreiberStack<T> {}
+
+impl<T> Default for TreiberStack<T> {
+    fn default() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+            hazards: AtomicPtr::new(ptr::null_mut()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            retired_count: AtomicUsize::new(0),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> TreiberStack<T> {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    // Снимок: к моменту возврата другие потоки могли изменить стек
+    fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    fn push(&self, data: T) {
+        let node = Box::into_raw(Box::new(NodeBox {
+            data: ManuallyDrop::new(data),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }));
+        // Голова не разыменовывается, поэтому hazard pointer не нужен
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: узел ещё не опубликован, им владеет только этот поток
+            unsafe { (*node).next.store(head, Ordering::Relaxed) };
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::SeqCst, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    fn pop(&self) -> Option<T> {
+        let guard = self.acquire_hazard();
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            if head.is_null() {
+                return None;
+            }
+            // После публикации hazard pointer голова перечитывается: если она не сменилась,
+            // узел был в стеке, когда его уже защищали, и освобождён не будет
+            guard.protect(head);
+            if self.head.load(Ordering::SeqCst) != head {
+                continue;
+            }
+
+            // SAFETY: head защищён hazard pointer
+            let next = unsafe { (*head).next.load(Ordering::Acquire) };
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                guard.clear();
+                // SAFETY: CAS снимает узел ровно у одного потока, данные забирает он. Другие
+                // потоки под hazard pointer читают только next
+                let data = unsafe { ManuallyDrop::into_inner(ptr::read(&(*head).data)) };
+                self.retire(head);
+                return Some(data);
+            }
+        }
+    }
+
+    fn acquire_hazard(&self) -> HazardGuard<'_> {
+        let mut slot = self.hazards.load(Ordering::Acquire);
+        // SAFETY: ячейки живут до удаления стека
+        while let Some(existing) = unsafe { slot.as_ref() } {
+            if !existing.active.load(Ordering::Relaxed)
+                && existing
+                    .active
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return HazardGuard { slot: existing };
+            }
+            slot = existing.next;
+        }
+
+        let slot = Box::into_raw(Box::new(HazardSlot {
+            protected: AtomicPtr::new(ptr::null_mut()),
+            active: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.hazards.load(Ordering::Acquire);
+        loop {
+            // SAFETY: ячейка ещё не опубликована
+            unsafe { (*slot).next = head };
+            match self
+                .hazards
+                .compare_exchange_weak(head, slot, Ordering::AcqRel, Ordering::Acquire)
+            {
+                // SAFETY: ячейки живут до удаления стека
+                Ok(_) => return HazardGuard { slot: unsafe { &*slot } },
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    // Узел уже снят со стека; его next больше не ведёт по стеку, и CAS с этим узлом у других
+    // потоков проваливается, поэтому next можно занять под список снятых
+    fn retire(&self, node: *mut NodeBox<T>) {
+        self.push_retired(node);
+        if self.retired_count.fetch_add(1, Ordering::Relaxed) + 1 >= RECLAIM_THRESHOLD {
+            self.reclaim();
+        }
+    }
+
+    fn push_retired(&self, node: *mut NodeBox<T>) {
+        let mut head = self.retired.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: снятый узел не освобождается, пока не пройдёт через reclaim
+            unsafe { (*node).next.store(head, Ordering::Relaxed) };
+            match self
+                .retired
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    // Забирает весь список снятых узлов, освобождает незащищённые, остальные возвращает
+    fn reclaim(&self) {
+        let mut node = self.retired.swap(ptr::null_mut(), Ordering::Acquire);
+
+        let mut protected = Vec::new();
+        let mut slot = self.hazards.load(Ordering::Acquire);
+        // SAFETY: ячейки живут до удаления стека
+        while let Some(existing) = unsafe { slot.as_ref() } {
+            let hazard = existing.protected.load(Ordering::SeqCst);
+            if !hazard.is_null() {
+                protected.push(hazard);
+            }
+            slot = existing.next;
+        }
+
+        let mut freed = 0;
+        while !node.is_null() {
+            // SAFETY: узел из списка снятых, освобождаем его только мы
+            let next = unsafe { (*node).next.load(Ordering::Relaxed) };
+            if protected.contains(&node.cast()) {
+                self.push_retired(node);
+            } else {
+                // SAFETY: узел снят со стека и не защищён: новые hazard pointer на него не
+                // пройдут проверку головы. Данные уже забраны, ManuallyDrop их не удалит
+                drop(unsafe { Box::from_raw(node) });
+                freed += 1;
+            }
+            node = next;
+        }
+        self.retired_count.fetch_sub(freed, Ordering::Relaxed);
+    }
+}
+
+impl<T> Drop for TreiberStack<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+
+        // Других потоков уже нет, все снятые узлы и ячейки можно освобождать
+        let mut node = *self.retired.get_mut();
+        while !node.is_null() {
+            // SAFETY: узел снят со стека, данные из него забраны
+            let retired = unsafe { Box::from_raw(node) };
+            node = retired.next.load(Ordering::Relaxed);
+        }
+        let mut slot = *self.hazards.get_mut();
+        while !slot.is_null() {
+            // SAFETY: ячейки выделены в acquire_hazard и больше никем не заняты
+            let hazard = unsafe { Box::from_raw(slot) };
+            slot = hazard.next;
+        }
+    }
+}
+
+fn main() {
+    let stack = TreiberStack::new();
+    std::thread::scope(|scope| {
+        for thread in 0..4 {
+            let stack = &stack;
+            scope.spawn(move || {
+                for value in 0..100 {
+                    stack.push(thread * 100 + value);
+                }
+            });
+        }
+    });
+
+    let mut values = Vec::new();
+    while let Some(value) = stack.pop() {
+        values.push(value);
+    }
+    values.sort_unstable();
+    assert_eq!(values, (0..400).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_push_pop_order() {
+    let stack = TreiberStack::new();
+    assert!(stack.is_empty());
+    assert_eq!(stack.pop(), None);
+
+    for value in 1..=3 {
+        stack.push(value);
+    }
+    assert!(!stack.is_empty());
+    assert_eq!(stack.pop(), Some(3));
+    stack.push(4);
+    assert_eq!(stack.pop(), Some(4));
+    assert_eq!(stack.pop(), Some(2));
+    assert_eq!(stack.pop(), Some(1));
+    assert_eq!(stack.pop(), None);
+}
+
+#[test]
+fn test_stack_is_send_and_sync() {
+    fn assert_send_sync<S: Send + Sync>() {}
+    assert_send_sync::<TreiberStack<String>>();
+}
+
+// Считает живые экземпляры, чтобы проверить, что значения не теряются и не удаляются дважды
+struct DropCounter<'a>(&'a AtomicUsize);
+
+impl<'a> DropCounter<'a> {
+    fn new(live: &'a AtomicUsize) -> Self {
+        live.fetch_add(1, Ordering::SeqCst);
+        DropCounter(live)
+    }
+}
+
+impl Drop for DropCounter<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_values_are_dropped_once() {
+    let live = AtomicUsize::new(0);
+    let stack = TreiberStack::new();
+    for _ in 0..RECLAIM_THRESHOLD * 3 {
+        stack.push(DropCounter::new(&live));
+    }
+    for _ in 0..RECLAIM_THRESHOLD * 2 {
+        drop(stack.pop());
+    }
+    assert_eq!(live.load(Ordering::SeqCst), RECLAIM_THRESHOLD);
+    drop(stack);
+    assert_eq!(live.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_concurrent_push_pop() {
+    const THREADS: usize = 8;
+    const OPS: usize = 2_000;
+
+    let stack = TreiberStack::new();
+    let popped: Vec<Vec<usize>> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let stack = &stack;
+                scope.spawn(move || {
+                    let mut popped = Vec::new();
+                    for op in 0..OPS {
+                        stack.push(thread * OPS + op);
+                        if op % 4 != 0 {
+                            popped.extend(stack.pop());
+                        }
+                    }
+                    popped
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("worker panicked"))
+            .collect()
+    });
+
+    let mut values: Vec<_> = popped.into_iter().flatten().collect();
+    while let Some(value) = stack.pop() {
+        values.push(value);
+    }
+    values.sort_unstable();
+    assert_eq!(values, (0..THREADS * OPS).collect::<Vec<_>>());
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StackOp {
+    Push(u32),
+    Pop(Option<u32>),
+}
+
+// Время вызова и возврата по общим часам; операции, интервалы которых пересекаются,
+// могли выполниться в любом порядке
+#[derive(Debug, Clone, Copy)]
+struct Event {
+    op: StackOp,
+    invoked: usize,
+    returned: usize,
+}
+
+// Ищет порядок операций, который согласуется с интервалами событий и с обычным стеком на Vec.
+// Следующей может идти только операция, вызванная раньше, чем завершилась любая ещё
+// не выполненная
+fn linearizable(events: &[Event], done: &mut [bool], model: &mut Vec<u32>) -> bool {
+    let Some(first_return) = (0..events.len())
+        .filter(|&i| !done[i])
+        .map(|i| events[i].returned)
+        .min()
+    else {
+        return true;
+    };
+
+    for i in 0..events.len() {
+        if done[i] || events[i].invoked > first_return {
+            continue;
+        }
+        match events[i].op {
+            StackOp::Push(value) => model.push(value),
+            StackOp::Pop(result) => {
+                if model.last().copied() != result {
+                    continue;
+                }
+                model.pop();
+            }
+        }
+
+        done[i] = true;
+        if linearizable(events, done, model) {
+            return true;
+        }
+        done[i] = false;
+        match events[i].op {
+            StackOp::Push(_) => {
+                model.pop();
+            }
+            StackOp::Pop(Some(value)) => model.push(value),
+            StackOp::Pop(None) => {}
+        }
+    }
+    false
+}
+
+#[test]
+fn test_linearizable_against_vec_model() {
+    use std::sync::Barrier;
+
+    const ROUNDS: usize = 300;
+    const THREADS: usize = 4;
+    const OPS: usize = 4;
+
+    for round in 0..ROUNDS {
+        let stack = TreiberStack::new();
+        // Стек стартует непустым, чтобы pop конкурировали за одни и те же узлы
+        let initial = [1_000, 1_001];
+        for value in initial {
+            stack.push(value);
+        }
+
+        let clock = AtomicUsize::new(0);
+        let barrier = Barrier::new(THREADS);
+        let events: Vec<Event> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..THREADS)
+                .map(|thread| {
+                    let (stack, clock, barrier) = (&stack, &clock, &barrier);
+                    scope.spawn(move || {
+                        // Линейный конгруэнтный генератор решает, push или pop
+                        let mut seed =
+                            ((round * THREADS + thread) as u32).wrapping_mul(2_654_435_761) | 1;
+                        let mut events = Vec::new();
+                        barrier.wait();
+                        for op in 0..OPS {
+                            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                            let invoked = clock.fetch_add(1, Ordering::SeqCst);
+                            let op = if seed >> 16 & 1 == 0 {
+                                let value = (thread * OPS + op) as u32;
+                                stack.push(value);
+                                StackOp::Push(value)
+                            } else {
+                                StackOp::Pop(stack.pop())
+                            };
+                            let returned = clock.fetch_add(1, Ordering::SeqCst);
+                            events.push(Event {
+                                op,
+                                invoked,
+                                returned,
+                            });
+                        }
+                        events
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("worker panicked"))
+                .collect()
+        });
+
+        let mut done = vec![false; events.len()];
+        let mut model = initial.to_vec();
+        assert!(
+            linearizable(&events, &mut done, &mut model),
+            "round {round}: history is not linearizable: {events:?}"
+        );
+    }
+}
@@ -3,25 +3,52 @@
 
 #![allow(unused)]
 
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::RangeBounds,
+    slice,
+};
+
+use linked_list::{
+    dot_escape, resolve_range, InvariantChecks, InvariantPolicy, LinkedList, ListError,
+};
+
+#[derive(Clone)]
 struct IndexedList<T> {
     data: Vec<Option<T>>,
     free_list: Vec<usize>,
+    // Для свободного слота — его место в free_list, чтобы забрать конкретный слот за O(1)
+    free_positions: Vec<Option<usize>>,
     head: Option<usize>,
     tail: Option<usize>,
+    len: usize,
+    invariants: InvariantChecks,
 }
 
-impl<T> IndexedList<T> {
-    fn new() -> Self {
+impl<T> Default for IndexedList<T> {
+    fn default() -> Self {
         Self {
             data: Vec::new(),
             free_list: Vec::new(),
+            free_positions: Vec::new(),
             head: None,
             tail: None,
+            len: 0,
+            invariants: InvariantChecks::default(),
         }
     }
+}
 
-    fn is_empty(&self) -> bool {
-        self.head.is_none()
+impl<T> IndexedList<T> {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_invariant_policy(mut self, policy: InvariantPolicy) -> Self {
+        self.invariants = InvariantChecks::new(policy);
+        self
     }
 
     fn indices(&self) -> impl Iterator<Item = usize> + '_ {
@@ -33,23 +60,31 @@ impl<T> IndexedList<T> {
         })
     }
 
-    fn iter(&self) -> impl Iterator<Item = &T> {
-        self.indices().map(move |i| self.data[i].as_ref().expect("Index refers to empty slot"))
+    // Порядок элементов совпадает с порядком занятых слотов в векторе
+    fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slots: self.data.iter(),
+            len: self.len,
+        }
     }
 
-    fn into_iter(self) -> IntoIter<T> {
-        let indices: Vec<usize> = self.indices().collect();
-        IntoIter {
-            indices,
-            data: self.data,
-            pos: 0,
+    fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.data.iter_mut(),
+            len: self.len,
         }
     }
 
+    // Проверка выполняется, только если этого требует политика списка
     fn check_invariants(&self) {
-        for &ind in &self.free_list {
+        if !self.invariants.due() {
+            return;
+        }
+
+        for (pos, &ind) in self.free_list.iter().enumerate() {
             assert!(ind < self.data.len(), "Free index out of bounds");
             assert!(self.data[ind].is_none(), "Free index is occupied");
+            assert_eq!(self.free_positions[ind], Some(pos), "Free index is not tracked");
         }
 
         if let Some(h) = self.head {
@@ -63,79 +98,249 @@ impl<T> IndexedList<T> {
         }
     }
 
+    // Граф для Graphviz: next здесь — переход к следующему занятому слоту от головы. Свободные
+    // слоты серые и пунктирные, занятые слоты, до которых не дойти от головы, обведены красным
+    fn write_dot(&self, out: &mut impl fmt::Write) -> fmt::Result
+    where
+        T: fmt::Debug,
+    {
+        let order: Vec<usize> = self.indices().collect();
+        let reachable = |ind: usize| self.head.is_some_and(|head| ind >= head);
+
+        writeln!(out, "digraph IndexedList {{")?;
+        writeln!(out, "    rankdir=LR;")?;
+        writeln!(out, "    node [shape=box];")?;
+        writeln!(out, "    head [shape=plaintext];")?;
+        writeln!(out, "    tail [shape=plaintext];")?;
+        for (ind, slot) in self.data.iter().enumerate() {
+            match slot {
+                Some(data) => {
+                    let label = dot_escape(&format!("#{ind}\n{data:?}"));
+                    let style = if reachable(ind) { "" } else { ", color=red" };
+                    writeln!(out, "    n{ind} [label=\"{label}\"{style}];")?;
+                }
+                None => writeln!(
+                    out,
+                    "    n{ind} [label=\"#{ind}\\nfree\", style=dashed, color=gray];"
+                )?,
+            }
+        }
+
+        if let Some(head) = self.head {
+            writeln!(out, "    head -> n{head};")?;
+        }
+        if let Some(tail) = self.tail {
+            writeln!(out, "    tail -> n{tail} [style=dotted];")?;
+        }
+        for pair in order.windows(2) {
+            writeln!(out, "    n{} -> n{};", pair[0], pair[1])?;
+        }
+
+        // free_list — стек: следующим займётся последний индекс
+        if !self.free_list.is_empty() {
+            writeln!(out, "    free [shape=plaintext];")?;
+            let mut prev = String::from("free");
+            for &ind in &self.free_list {
+                writeln!(out, "    {prev} -> n{ind} [style=dotted, color=gray];")?;
+                prev = format!("n{ind}");
+            }
+        }
+        writeln!(out, "}}")
+    }
+
+    fn to_dot(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut out = String::new();
+        self.write_dot(&mut out).expect("writing to a String does not fail");
+        out
+    }
+
+    // Строка на слот вектора по порядку, затем содержимое free_list
+    fn write_ascii_diagram(&self, out: &mut impl fmt::Write) -> fmt::Result
+    where
+        T: fmt::Debug,
+    {
+        let slot_name = |ind: Option<usize>| ind.map_or("None".to_string(), |ind| format!("#{ind}"));
+        writeln!(
+            out,
+            "head -> {}, tail -> {}",
+            slot_name(self.head),
+            slot_name(self.tail)
+        )?;
+        for (ind, slot) in self.data.iter().enumerate() {
+            match slot {
+                Some(data) if self.head.is_some_and(|head| ind >= head) => {
+                    let next = self.find_next_occupied(ind);
+                    writeln!(out, "#{ind} {data:?} -> {}", slot_name(next))?;
+                }
+                Some(data) => writeln!(out, "#{ind} {data:?} (unreachable)")?,
+                None => writeln!(out, "#{ind} free")?,
+            }
+        }
+        if !self.free_list.is_empty() {
+            let free: Vec<_> = self.free_list.iter().map(|ind| format!("#{ind}")).collect();
+            writeln!(out, "free list: {}", free.join(" "))?;
+        }
+        Ok(())
+    }
+
+    fn to_ascii_diagram(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let mut out = String::new();
+        self.write_ascii_diagram(&mut out)
+            .expect("writing to a String does not fail");
+        out
+    }
+
     fn allocate_index(&mut self) -> usize {
         if let Some(ind) = self.free_list.pop() {
+            self.free_positions[ind] = None;
             ind
         } else {
-            self.data.push(None);
-            self.data.len() - 1
+            self.push_slot()
         }
     }
 
-    fn find_next_occupied(&self, start: usize) -> Option<usize> {
-        for i in (start + 1)..self.data.len() {
-            if self.data[i].is_some() {
-                return Some(i);
-            }
+    // Новый слот в конце вектора, сразу занятый
+    fn push_slot(&mut self) -> usize {
+        self.data.push(None);
+        self.free_positions.push(None);
+        self.data.len() - 1
+    }
+
+    fn free_index(&mut self, index: usize) {
+        self.free_positions[index] = Some(self.free_list.len());
+        self.free_list.push(index);
+    }
+
+    // Забирает конкретный свободный индекс из free_list
+    fn claim_index(&mut self, index: usize) {
+        let pos = self.free_positions[index].take().expect("Index is not free");
+        self.free_list.swap_remove(pos);
+        if let Some(&moved) = self.free_list.get(pos) {
+            self.free_positions[moved] = Some(pos);
         }
-        None
     }
 
-    fn find_previous_occupied(&self, start: usize) -> Option<usize> {
-        for i in (0..start).rev() {
-            if self.data[i].is_some() {
-                return Some(i);
-            }
+    // Добавляет свободные слоты перед головой: их столько же, сколько слотов уже есть, поэтому
+    // сдвиг индексов случается редко и push_head в среднем O(1)
+    fn grow_front(&mut self) {
+        let extra = self.data.len().max(1);
+        self.data.splice(0..0, std::iter::repeat_with(|| None).take(extra));
+        self.free_positions.splice(0..0, std::iter::repeat_n(None, extra));
+        for ind in self.free_list.iter_mut() {
+            *ind += extra;
+        }
+        self.head = self.head.map(|h| h + extra);
+        self.tail = self.tail.map(|t| t + extra);
+        for ind in 0..extra {
+            self.free_index(ind);
         }
-        None
     }
 
-    fn push_head(&mut self, value: T) -> usize {
-        let index = self.allocate_index();
-        self.data[index] = Some(value);
+    // Очередь (push_tail + pop_head) оставляет перед головой свободные слоты. Когда их вдвое
+    // больше, чем элементов, сдвигаем занятые слоты к началу вектора
+    fn compact_front(&mut self) {
+        let Some(head) = self.head else {
+            return;
+        };
+        if head <= 2 * self.len {
+            return;
+        }
 
-        if self.head.is_none() {
-            self.head = Some(index);
-            self.tail = Some(index);
+        self.data.drain(..head);
+        self.free_positions.drain(..head);
+        self.free_list.retain(|&ind| ind >= head);
+        for (pos, ind) in self.free_list.iter_mut().enumerate() {
+            *ind -= head;
+            self.free_positions[*ind] = Some(pos);
         }
+        self.head = Some(0);
+        self.tail = self.tail.map(|t| t - head);
+    }
 
-        index
+    fn find_next_occupied(&self, start: usize) -> Option<usize> {
+        ((start + 1)..self.data.len()).find(|&i| self.data[i].is_some())
     }
 
-    fn push_tail(&mut self, value: T) -> usize {
-        let index = self.allocate_index();
-        self.data[index] = Some(value);
+    fn find_previous_occupied(&self, start: usize) -> Option<usize> {
+        (0..start).rev().find(|&i| self.data[i].is_some())
+    }
 
-        if self.head.is_none() {
-            self.head = Some(index);
-            self.tail = Some(index);
-        } else {
-            self.tail = Some(index);
+    // Освобождает занятый слот и поправляет голову и хвост
+    fn take_slot(&mut self, index: usize) -> T {
+        let value = self.data[index].take().expect("Index refers to empty slot");
+        self.free_index(index);
+        self.len -= 1;
+
+        if Some(index) == self.head {
+            self.head = self.find_next_occupied(index);
         }
+        if Some(index) == self.tail {
+            self.tail = self.find_previous_occupied(index);
+        }
+        value
+    }
 
-        index
+    fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_mut(|value| f(value));
     }
 
-    fn pop_head(&mut self) -> Option<T> {
-        let head_index = self.head?;
-        let value = self.data[head_index].take()?;
+    fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        self.extract_if(|value| !f(value)).for_each(drop);
+    }
 
-        self.free_list.push(head_index);
-        self.head = self.find_next_occupied(head_index);
+    fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, pred: F) -> ExtractIf<'_, T, F> {
+        ExtractIf {
+            next: self.head,
+            list: self,
+            pred,
+        }
+    }
+
+    fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T> {
+        let range = resolve_range(range, self.len);
+        let next = self.indices().nth(range.start);
+        Drain {
+            list: self,
+            next,
+            remaining: range.len(),
+        }
+    }
+
+    fn push_tail(&mut self, value: T) -> usize {
+        // Порядок элементов совпадает с порядком в векторе, поэтому новый хвост
+        // должен лежать правее старого
+        let index = match self.tail {
+            Some(tail) if tail + 1 < self.data.len() => {
+                self.claim_index(tail + 1);
+                tail + 1
+            }
+            Some(_) => self.push_slot(),
+            None => self.allocate_index(),
+        };
+        self.data[index] = Some(value);
 
         if self.head.is_none() {
-            self.tail = None;
+            self.head = Some(index);
         }
+        self.tail = Some(index);
+        self.len += 1;
 
-        Some(value)
+        index
     }
 
     fn pop_tail(&mut self) -> Option<T> {
         let tail_index = self.tail?;
         let value = self.data[tail_index].take()?;
 
-        self.free_list.push(tail_index);
+        self.free_index(tail_index);
         self.tail = self.find_previous_occupied(tail_index);
+        self.len -= 1;
 
         if self.tail.is_none() {
             self.head = None;
@@ -143,29 +348,74 @@ impl<T> IndexedList<T> {
 
         Some(value)
     }
+}
 
-    fn remove_at(&mut self, position: usize) -> Option<T> {
-        if position == 0 {
-            return self.pop_head();
+impl<T> LinkedList<T> for IndexedList<T> {
+    fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn peek_head(&self) -> Option<&T> {
+        self.head.and_then(|ind| self.data[ind].as_ref())
+    }
+
+    fn push_head(&mut self, value: T) {
+        let index = match self.head {
+            Some(head) => {
+                // Слева от головы места нет: добавляем запас свободных слотов
+                if head == 0 {
+                    self.grow_front();
+                }
+                let head = self.head.expect("head is only shifted");
+                self.claim_index(head - 1);
+                head - 1
+            }
+            None => self.allocate_index(),
+        };
+        self.data[index] = Some(value);
+
+        if self.tail.is_none() {
+            self.tail = Some(index);
         }
+        self.head = Some(index);
+        self.len += 1;
+    }
 
-        let index = self.indices().nth(position)?;
+    fn pop_head(&mut self) -> Option<T> {
+        let head_index = self.head?;
+        let value = self.data[head_index].take()?;
 
-        if index >= self.data.len() {
-            return None;
+        self.free_index(head_index);
+        self.head = self.find_next_occupied(head_index);
+        self.len -= 1;
+
+        if self.head.is_none() {
+            self.tail = None;
         }
+        self.compact_front();
 
-        let value = self.data[index].take()?;
-        self.free_list.push(index);
+        Some(value)
+    }
 
-        if Some(index) == self.head {
-            self.head = self.find_next_occupied(index);
+    fn try_remove_at(&mut self, position: usize) -> Result<T, ListError> {
+        if self.is_empty() {
+            return Err(ListError::Empty);
         }
-        if Some(index) == self.tail {
-            self.tail = self.find_previous_occupied(index);
+
+        if position == 0 {
+            return self.pop_head().ok_or(ListError::Empty);
         }
 
-        Some(value)
+        let index = self.indices().nth(position).ok_or(ListError::OutOfBounds {
+            position,
+            len: self.len,
+        })?;
+
+        Ok(self.take_slot(index))
     }
 
     fn join(mut self, mut other: Self) -> Self {
@@ -176,7 +426,7 @@ impl<T> IndexedList<T> {
             return self;
         }
 
-        let mut result = IndexedList::new();
+        let mut result = IndexedList::new().with_invariant_policy(self.invariants.policy());
 
         while let Some(item) = self.pop_head() {
             result.push_tail(item);
@@ -188,9 +438,15 @@ impl<T> IndexedList<T> {
         result
     }
 
-    fn divide_at(mut self, position: usize) -> (Self, Self) {
-        let mut left = IndexedList::new();
-        let mut right = IndexedList::new();
+    fn try_divide_at(mut self, position: usize) -> Result<(Self, Self), ListError> {
+        let len = self.len;
+        if position > len {
+            return Err(ListError::OutOfBounds { position, len });
+        }
+
+        let policy = self.invariants.policy();
+        let mut left = IndexedList::new().with_invariant_policy(policy);
+        let mut right = IndexedList::new().with_invariant_policy(policy);
 
         let mut current_pos = 0;
 
@@ -203,62 +459,247 @@ impl<T> IndexedList<T> {
             current_pos += 1;
         }
 
-        (left, right)
+        Ok((left, right))
     }
 
-    fn append_at(&mut self, position: usize, value: T) {
-        let len = self.iter().count();
+    fn try_append_at(&mut self, position: usize, value: T) -> Result<(), ListError> {
+        let len = self.len;
+        if position > len {
+            return Err(ListError::OutOfBounds { position, len });
+        }
 
         if position == 0 {
             self.push_head(value);
-            return;
+            return Ok(());
         }
-        if position >= len {
+        if position == len {
             self.push_tail(value);
-            return;
+            return Ok(());
         }
 
-        let indices: Vec<usize> = self.indices().collect();
-        let mut items = Vec::with_capacity(len + 1);
-
-        for &idx in indices.iter().take(position) {
-            items.push(self.data[idx].take().expect("Expected to have a value"));
-        }
-
-        items.push(value);
-
-        for &idx in indices.iter().skip(position) {
-            items.push(self.data[idx].take().expect("Expected to have a value"));
-        }
-
-        self.data.clear();
-        self.free_list.clear();
-        self.head = None;
-        self.tail = None;
-
-        for item in items {
-            self.push_tail(item);
-        }
+        // Новый элемент встаёт между соседями. Если между ними нет свободного слота, сдвигаем
+        // на один слот ту сторону, где свободный слот ближе
+        let after = self.indices().nth(position).expect("position is less than len");
+        let before = self.find_previous_occupied(after).expect("position is not zero");
+        let left = (0..after).rev().find(|&i| self.data[i].is_none());
+        let right = (after..self.data.len()).find(|&i| self.data[i].is_none());
+
+        let index = match (left, right) {
+            (Some(free), _) if free > before => {
+                self.claim_index(free);
+                free
+            }
+            (Some(free), right) if right.is_none_or(|r| before - free < r - after) => {
+                self.claim_index(free);
+                self.data[free..=before].rotate_left(1);
+                if self.head.is_some_and(|h| h > free) {
+                    self.head = self.head.map(|h| h - 1);
+                }
+                before
+            }
+            (_, Some(free)) => {
+                self.claim_index(free);
+                self.data[after..=free].rotate_right(1);
+                if self.tail.is_some_and(|t| t < free) {
+                    self.tail = self.tail.map(|t| t + 1);
+                }
+                after
+            }
+            (_, None) => {
+                let free = self.push_slot();
+                self.data[after..=free].rotate_right(1);
+                self.tail = self.tail.map(|t| t + 1);
+                after
+            }
+        };
+        self.data[index] = Some(value);
+        self.len += 1;
+        Ok(())
     }
 }
 
+// Ещё не выданные элементы лежат в indices[pos..end]
 struct IntoIter<T> {
     indices: Vec<usize>,
     data: Vec<Option<T>>,
     pos: usize,
+    end: usize,
 }
 
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.pos >= self.indices.len() {
+        if self.pos >= self.end {
             return None;
         }
         let ind = self.indices[self.pos];
         self.pos += 1;
         self.data[ind].take()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.pos;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        self.data[self.indices[self.end]].take()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+// Освобождает подходящие слоты по мере обхода
+struct ExtractIf<'a, T, F: FnMut(&mut T) -> bool> {
+    list: &'a mut IndexedList<T>,
+    next: Option<usize>,
+    pred: F,
+}
+
+impl<T, F: FnMut(&mut T) -> bool> Iterator for ExtractIf<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(ind) = self.next {
+            self.next = self.list.find_next_occupied(ind);
+            let value = self.list.data[ind].as_mut().expect("Index refers to empty slot");
+            if (self.pred)(value) {
+                return Some(self.list.take_slot(ind));
+            }
+        }
+        None
+    }
+}
+
+// Недочитанные элементы диапазона удаляются при drop, как у Vec::drain
+struct Drain<'a, T> {
+    list: &'a mut IndexedList<T>,
+    next: Option<usize>,
+    remaining: usize,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let ind = self.next?;
+        self.next = self.list.find_next_occupied(ind);
+        Some(self.list.take_slot(ind))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        for _ in self.by_ref() {}
+    }
+}
+
+struct Iter<'a, T> {
+    slots: slice::Iter<'a, Option<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.slots.find_map(Option::as_ref)?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let item = self.slots.by_ref().rev().find_map(Option::as_ref)?;
+        self.len -= 1;
+        Some(item)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+struct IterMut<'a, T> {
+    slots: slice::IterMut<'a, Option<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.slots.find_map(Option::as_mut)?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let item = self.slots.by_ref().rev().find_map(Option::as_mut)?;
+        self.len -= 1;
+        Some(item)
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> IntoIterator for IndexedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let indices: Vec<usize> = self.indices().collect();
+        IntoIter {
+            end: indices.len(),
+            indices,
+            data: self.data,
+            pos: 0,
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a IndexedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut IndexedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
 }
 
 impl<T> FromIterator<T> for IndexedList<T> {
@@ -284,6 +725,66 @@ impl<T> Extend<T> for IndexedList<T> {
     }
 }
 
+impl<'a, T: Copy + 'a> Extend<&'a T> for IndexedList<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T: PartialEq> PartialEq for IndexedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for IndexedList<T> {}
+
+impl<T: PartialOrd> PartialOrd for IndexedList<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord> Ord for IndexedList<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Hash> Hash for IndexedList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for data in self.iter() {
+            data.hash(state);
+        }
+        state.write_usize(self.len);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IndexedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+fn create_and_drop_large_list() {
+    let mut list = IndexedList::new();
+    list.extend(0..1_000_000);
+}
+
+fn main() {
+    let mut list = IndexedList::new();
+    list.push_head(1);
+    list.push_head(2);
+    list.push_head(3);
+    let values: Vec<i32> = list.iter().copied().collect();
+    assert_eq!(values, vec![3, 2, 1]);
+
+    list.pop_head();
+    list.pop_head();
+    let values2: Vec<i32> = list.iter().copied().collect();
+    assert_eq!(values2, vec![1]);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,12 +797,12 @@ mod tests {
         list.push_head(3);
 
         let values: Vec<i32> = list.iter().copied().collect();
-        assert_eq!(values, vec![1, 2, 3]);
+        assert_eq!(values, vec![3, 2, 1]);
 
         list.pop_head();
         list.pop_head();
         let values2: Vec<i32> = list.iter().copied().collect();
-        assert_eq!(values2, vec![3]);
+        assert_eq!(values2, vec![1]);
     }
 
     #[test]
@@ -334,7 +835,7 @@ mod tests {
         let joined = a.join(b);
 
         let values: Vec<_> = joined.iter().copied().collect();
-        assert_eq!(values, vec![1, 2, 3, 4]);
+        assert_eq!(values, vec![2, 1, 4, 3]);
     }
 
     #[test]
@@ -346,7 +847,7 @@ mod tests {
         list.push_tail(4);
         list.push_tail(5);
 
-        let (a, b) = list.divide_at(3);
+        let (a, b) = list.divide_at(3).expect("divide_at failed");
 
         let left: Vec<_> = a.iter().copied().collect();
         let right: Vec<_> = b.iter().copied().collect();
@@ -364,7 +865,33 @@ mod tests {
 
         list.append_at(1, 9);
         let collected: Vec<_> = list.iter().copied().collect();
-        assert_eq!(collected, vec![1, 9, 2, 3]);
+        assert_eq!(collected, vec![3, 9, 2, 1]);
+    }
+
+    #[test]
+    fn test_append_at_in_place() {
+        let mut list = IndexedList::new().with_invariant_policy(InvariantPolicy::Always);
+        list.extend(0..8);
+        list.remove_at(2);
+        list.pop_head();
+
+        // Свободный слот между соседями занимается без сдвига
+        list.append_at(1, 20);
+        assert_eq!(list.data[2], Some(20));
+        // Сдвигается только сторона с ближайшим свободным слотом
+        list.append_at(2, 30);
+        assert_eq!(list.data[0..4], [Some(1), Some(20), Some(30), Some(3)]);
+        assert_eq!(list.head, Some(0));
+        list.append_at(4, 40);
+        assert_eq!(list.data.len(), 9);
+        list.append_at(6, 60);
+        assert_eq!(list.data.len(), 10);
+
+        list.check_invariants();
+        assert!(list.free_list.is_empty());
+        assert_eq!(list.tail, Some(9));
+        let values: Vec<_> = list.iter().copied().collect();
+        assert_eq!(values, vec![1, 20, 30, 3, 40, 4, 60, 5, 6, 7]);
     }
 
     #[test]
@@ -377,7 +904,7 @@ mod tests {
 
         list.remove_at(3);
         let collected: Vec<_> = list.iter().copied().collect();
-        assert_eq!(collected, vec![1, 2, 3]);
+        assert_eq!(collected, vec![4, 3, 2]);
     }
 
     #[test]
@@ -386,23 +913,210 @@ mod tests {
         let values: Vec<_> = list.iter().copied().collect();
         assert_eq!(values, vec![0, 1, 2, 3, 4]);
     }
-}
 
-fn create_and_drop_large_list() {
-    let mut list = IndexedList::new();
-    list.extend(0..1_000_000);
-}
+    #[test]
+    fn test_push_reuses_free_slots() {
+        let mut list: IndexedList<i32> = (0..4).collect();
+        list.pop_head();
+        list.pop_tail();
+        list.push_tail(4);
+        list.push_head(5);
+        list.push_head(6);
 
-fn main() {
-    let mut list = IndexedList::new();
-    list.push_head(1);
-    list.push_head(2);
-    list.push_head(3);
-    let values: Vec<i32> = list.iter().copied().collect();
-    assert_eq!(values, vec![1, 2, 3]);
+        let values: Vec<_> = list.iter().copied().collect();
+        assert_eq!(values, vec![6, 5, 1, 2, 4]);
+        list.check_invariants();
+    }
 
-    list.pop_head();
-    list.pop_head();
-    let values2: Vec<i32> = list.iter().copied().collect();
-    assert_eq!(values2, vec![3]);
+    #[test]
+    fn test_push_head_grows_front_geometrically() {
+        let mut list = IndexedList::new().with_invariant_policy(InvariantPolicy::Always);
+        for value in 0..1000 {
+            list.push_head(value);
+        }
+        list.check_invariants();
+        // Запас удваивается, поэтому слотов не больше чем вдвое больше элементов
+        assert!(list.data.len() < 2 * 1000);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), (0..1000).rev().collect::<Vec<_>>());
+
+        for value in 1000..1010 {
+            list.push_tail(value);
+        }
+        assert_eq!(list.remove_at(500), Some(499));
+        list.check_invariants();
+        assert_eq!(list.len(), 1009);
+    }
+
+    #[test]
+    fn test_queue_reuses_slots() {
+        let mut list = IndexedList::new().with_invariant_policy(InvariantPolicy::Always);
+        list.push_tail(0);
+        for value in 1..100_000 {
+            list.push_tail(value);
+            assert_eq!(list.pop_head(), Some(value - 1));
+        }
+        list.check_invariants();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![99_999]);
+        assert!(list.data.len() <= 4);
+        assert!(list.free_list.len() <= 3);
+
+        // Свободные слоты в середине переживают сдвиг
+        let mut list: IndexedList<i32> = (0..10).collect();
+        list.remove_at(8);
+        for _ in 0..7 {
+            list.pop_head();
+        }
+        list.check_invariants();
+        assert_eq!(list.data.len(), 3);
+        assert_eq!(list.free_list, vec![1]);
+        list.push_tail(10);
+        list.append_at(1, 8);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_structure_dumps() {
+        let mut list: IndexedList<&str> = ["a", "b", "c", "d"].into_iter().collect();
+        assert_eq!(list.remove_at(1), Some("b"));
+        assert_eq!(list.pop_head(), Some("a"));
+        assert_eq!(
+            list.to_ascii_diagram(),
+            "head -> #2, tail -> #3\n#0 free\n#1 free\n#2 \"c\" -> #3\n#3 \"d\" -> None\n\
+             free list: #1 #0\n"
+        );
+
+        let dot = list.to_dot();
+        assert!(dot.starts_with("digraph IndexedList {\n"));
+        assert!(dot.contains("    n0 [label=\"#0\\nfree\", style=dashed, color=gray];\n"));
+        assert!(dot.contains("    n2 [label=\"#2\\n\\\"c\\\"\"];\n"));
+        assert!(dot.contains("    head -> n2;\n    tail -> n3 [style=dotted];\n    n2 -> n3;\n"));
+        assert!(dot.contains("    free -> n1 [style=dotted, color=gray];\n    n1 -> n0 [style"));
+
+        // Голова указывает мимо занятого слота
+        list.data[0] = Some("x");
+        assert!(list.to_ascii_diagram().contains("\n#0 \"x\" (unreachable)\n"));
+        assert!(list.to_dot().contains("    n0 [label=\"#0\\n\\\"x\\\"\", color=red];\n"));
+
+        let empty: IndexedList<i32> = IndexedList::new();
+        assert_eq!(empty.to_ascii_diagram(), "head -> None, tail -> None\n");
+    }
+
+    #[test]
+    fn test_invariant_policy() {
+        // Свободный индекс за пределами вектора нарушает инвариант
+        let corrupt = |policy| {
+            let mut list = (0..3).collect::<IndexedList<i32>>().with_invariant_policy(policy);
+            list.free_list.push(10);
+            list
+        };
+        corrupt(InvariantPolicy::Never).check_invariants();
+
+        let list = corrupt(InvariantPolicy::EveryN(2));
+        let checked = std::panic::catch_unwind(|| list.check_invariants());
+        assert!(checked.is_err());
+        list.check_invariants();
+
+        let (left, right) = (0..4)
+            .collect::<IndexedList<i32>>()
+            .with_invariant_policy(InvariantPolicy::Never)
+            .divide_at(2)
+            .expect("position is in bounds");
+        assert_eq!(left.join(right).invariants.policy(), InvariantPolicy::Never);
+    }
+
+    #[test]
+    #[should_panic(expected = "Free index out of bounds")]
+    fn test_invariant_policy_always_checks() {
+        let mut list = (0..3)
+            .collect::<IndexedList<i32>>()
+            .with_invariant_policy(InvariantPolicy::Always);
+        list.free_list.push(10);
+        list.check_invariants();
+    }
+
+    #[test]
+    fn test_for_loops() {
+        let mut list: IndexedList<i32> = (0..=3).collect();
+        list.pop_head();
+        for data in &mut list {
+            *data *= 10;
+        }
+
+        let mut seen = Vec::new();
+        for data in &list {
+            seen.push(*data);
+        }
+        assert_eq!(seen, vec![10, 20, 30]);
+
+        let owned: Vec<_> = list.into_iter().collect();
+        assert_eq!(owned, vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn test_retain_and_extract_if() {
+        let mut list: IndexedList<i32> = (1..=10).collect();
+        list.retain(|&value| value % 3 != 0);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 4, 5, 7, 8, 10]);
+        assert_eq!(list.free_list.len(), 3);
+
+        list.retain_mut(|value| {
+            *value *= 10;
+            *value != 10
+        });
+        let big: Vec<_> = list.extract_if(|value| *value > 60).collect();
+        assert_eq!(big, vec![70, 80, 100]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![20, 40, 50]);
+        list.check_invariants();
+
+        list.push_tail(60);
+        list.push_head(10);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![10, 20, 40, 50, 60]);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut list: IndexedList<i32> = (0..8).collect();
+        let drained: Vec<_> = list.drain(2..5).collect();
+        assert_eq!(drained, vec![2, 3, 4]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 5, 6, 7]);
+
+        let mut drain = list.drain(3..);
+        assert_eq!(drain.next(), Some(6));
+        drop(drain);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 5]);
+        assert_eq!(list.tail, Some(5));
+        list.check_invariants();
+
+        assert_eq!(list.drain(..).len(), 3);
+        assert!(list.is_empty());
+        assert_eq!(list.tail, None);
+    }
+
+    #[test]
+    fn test_double_ended_iterators() {
+        let mut list: IndexedList<i32> = (1..=5).collect();
+        list.remove_at(2);
+        list.push_head(0);
+        assert_eq!(list.len(), 5);
+
+        let values: Vec<_> = list.iter().rev().copied().collect();
+        assert_eq!(values, vec![5, 4, 2, 1, 0]);
+
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.len(), 3);
+
+        for data in list.iter_mut().rev().take(2) {
+            *data *= 10;
+        }
+
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next_back(), Some(50));
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.rev().collect::<Vec<_>>(), vec![40, 2, 1]);
+    }
+
+    linked_list::conformance_tests!(IndexedList<i32>);
 }